@@ -0,0 +1,314 @@
+use crate::{Canvas, Color, Direction, Point, Step};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// unpainted, black, white and the robot
+const PALETTE: [[u8; 3]; 4] = [[64, 64, 64], [0, 0, 0], [255, 255, 255], [220, 40, 40]];
+const ROBOT: u8 = 3;
+
+fn index(color: Color) -> u8 {
+    match color {
+        Color::Unpainted => 0,
+        Color::Black => 1,
+        Color::White => 2,
+    }
+}
+
+// A picture of the hull as palette indices, every panel is `scale` by `scale` pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Raster {
+    // the part of the canvas between the corners `min` and `max`
+    pub fn new(canvas: &Canvas, (min, max): (Point, Point), scale: usize) -> Raster {
+        assert!(scale > 0, "scale must be at least one");
+        let width = (max.x - min.x + 1) as usize * scale;
+        let height = (max.y - min.y + 1) as usize * scale;
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let pos = Point { x: min.x + (x / scale) as isize, y: min.y + (y / scale) as isize };
+                pixels.push(match canvas.robot {
+                    Some((robot, _)) if robot == pos => ROBOT,
+                    _ => index(canvas.get(pos)),
+                });
+            }
+        }
+        Raster { width, height, pixels }
+    }
+
+    pub fn from_canvas(canvas: &Canvas, scale: usize) -> Raster {
+        match canvas.bounds() {
+            Some(bounds) => Raster::new(canvas, bounds, scale),
+            None => Raster { width: 0, height: 0, pixels: Vec::new() },
+        }
+    }
+
+    pub fn rgb(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|pixel| PALETTE[*pixel as usize].iter().cloned()).collect()
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut bytes = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        bytes.extend(self.rgb());
+        bytes
+    }
+
+    // an indexed PNG with stored (uncompressed) deflate blocks
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut bytes = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        let mut header = Vec::new();
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bit palette, default compression, filter and no interlacing
+        header.extend_from_slice(&[8, 3, 0, 0, 0]);
+        chunk(&mut bytes, b"IHDR", &header);
+        chunk(&mut bytes, b"PLTE", &PALETTE.concat());
+
+        let mut raw = Vec::with_capacity((self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(0xffff).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(block) = blocks.next() {
+            zlib.push(if blocks.peek().is_none() { 1 } else { 0 });
+            zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+            zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+            zlib.extend_from_slice(block);
+        }
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+        chunk(&mut bytes, b"IDAT", &zlib);
+        chunk(&mut bytes, b"IEND", &[]);
+        bytes
+    }
+
+    // one rectangle per pixel that is not unpainted, on an unpainted background
+    pub fn to_svg(&self) -> String {
+        let color = |pixel: u8| {
+            let [r, g, b] = PALETTE[pixel as usize];
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        };
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" \
+             shape-rendering=\"crispEdges\">\n<rect width=\"{w}\" height=\"{h}\" fill=\"{}\"/>\n",
+            color(0),
+            w = self.width,
+            h = self.height
+        );
+        for (i, pixel) in self.pixels.iter().enumerate() {
+            if *pixel != 0 {
+                let (x, y) = (i % self.width, i / self.width);
+                let fill = color(*pixel);
+                svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" fill=\"{}\"/>\n", x, y, fill));
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in bytes.iter() {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes.iter() {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn chunk(bytes: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = bytes.len();
+    bytes.extend_from_slice(kind);
+    bytes.extend_from_slice(data);
+    let crc = crc32(&bytes[start..]);
+    bytes.extend_from_slice(&crc.to_be_bytes());
+}
+
+// LZW with a clear code after every two pixels, so the codes never grow past three bits.
+// That is not small, but needs no dictionary.
+fn lzw(pixels: &[u8]) -> Vec<u8> {
+    const CLEAR: u32 = 4;
+    const END: u32 = 5;
+    let mut bytes = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    let mut emit = |code: u32, bytes: &mut Vec<u8>| {
+        buffer |= code << bits;
+        bits += 3;
+        while bits >= 8 {
+            bytes.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    };
+    for pair in pixels.chunks(2) {
+        emit(CLEAR, &mut bytes);
+        for pixel in pair.iter() {
+            emit(u32::from(*pixel), &mut bytes);
+        }
+    }
+    emit(END, &mut bytes);
+    if bits > 0 {
+        bytes.push(buffer as u8);
+    }
+    bytes
+}
+
+// An endlessly looping animation, `delay` is in hundredths of a second. All frames need the
+// size of the first one.
+pub fn gif(frames: &[Raster], delay: u16) -> Vec<u8> {
+    let (width, height) = frames.first().map_or((0, 0), |frame| (frame.width, frame.height));
+    assert!(width <= 0xffff && height <= 0xffff, "too large for a gif");
+    let mut bytes = b"GIF89a".to_vec();
+    bytes.extend_from_slice(&(width as u16).to_le_bytes());
+    bytes.extend_from_slice(&(height as u16).to_le_bytes());
+    // global color table with four colors
+    bytes.extend_from_slice(&[0xf1, 0, 0]);
+    bytes.extend_from_slice(&PALETTE.concat());
+    bytes.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+    for frame in frames.iter() {
+        assert_eq!((width, height), (frame.width, frame.height), "frames differ in size");
+        bytes.extend_from_slice(&[0x21, 0xf9, 4, 0]);
+        bytes.extend_from_slice(&delay.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&[0x2c, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&(width as u16).to_le_bytes());
+        bytes.extend_from_slice(&(height as u16).to_le_bytes());
+        bytes.push(0);
+        bytes.push(2);
+        for block in lzw(&frame.pixels).chunks(255) {
+            bytes.push(block.len() as u8);
+            bytes.extend_from_slice(block);
+        }
+        bytes.push(0);
+    }
+    bytes.push(0x3b);
+    bytes
+}
+
+// Replays the steps of paint_recorded, the first frame is the hull before the robot moved.
+// Every frame covers the whole painting.
+pub fn frames(start: Color, steps: &[Step], scale: usize) -> Vec<Raster> {
+    let mut canvas = Canvas::new();
+    if start != Color::Unpainted {
+        canvas.paint(Point { x: 0, y: 0 }, start);
+    }
+    canvas.robot = Some((Point { x: 0, y: 0 }, Direction::Up));
+    let mut last = canvas.clone();
+    for step in steps.iter() {
+        last.paint(step.pos, step.color);
+        last.robot = Some(step.robot);
+    }
+    let bounds = match last.bounds() {
+        Some(bounds) => bounds,
+        None => return Vec::new(),
+    };
+
+    let mut frames = vec![Raster::new(&canvas, bounds, scale)];
+    for step in steps.iter() {
+        canvas.paint(step.pos, step.color);
+        canvas.robot = Some(step.robot);
+        frames.push(Raster::new(&canvas, bounds, scale));
+    }
+    frames
+}
+
+// picks the format by the extension: ppm, png, svg or gif (a single frame)
+pub fn save<P: AsRef<Path>>(raster: &Raster, path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    let bytes = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("ppm") => raster.to_ppm(),
+        Some("png") => raster.to_png(),
+        Some("svg") => raster.to_svg().into_bytes(),
+        Some("gif") => gif(std::slice::from_ref(raster), 0),
+        _ => {
+            let message = format!("{}: unknown image format", path.display());
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+    };
+    fs::write(path, bytes)
+}
+
+// writes frame_00000.<extension>, frame_00001.<extension>, ... into `dir`
+pub fn save_frames<P: AsRef<Path>>(frames: &[Raster], dir: P, extension: &str) -> io::Result<()> {
+    fs::create_dir_all(&dir)?;
+    for (i, frame) in frames.iter().enumerate() {
+        save(frame, dir.as_ref().join(format!("frame_{:05}.{}", i, extension)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::{crc32, frames, gif, lzw, Raster};
+    use crate::{paint_recorded, Canvas, Color, Direction, Point};
+    use intcomputer::intcode::Amplifier;
+
+    fn example() -> Canvas {
+        let mut canvas = Canvas::new();
+        canvas.paint(Point { x: 0, y: 0 }, Color::White);
+        canvas.paint(Point { x: 1, y: 1 }, Color::Black);
+        canvas.robot = Some((Point { x: 1, y: 0 }, Direction::Up));
+        canvas
+    }
+
+    #[test]
+    fn formats() {
+        let raster = Raster::from_canvas(&example(), 2);
+        assert_eq!((4, 4), (raster.width, raster.height));
+        assert_eq!(&[2, 2, 3, 3, 2, 2, 3, 3, 0, 0, 1, 1], &raster.pixels[..12]);
+
+        let ppm = raster.to_ppm();
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(b"P6\n4 4\n255\n".len() + 4 * 4 * 3, ppm.len());
+
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        let png = raster.to_png();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+
+        let svg = Raster::from_canvas(&example(), 1).to_svg();
+        assert_eq!(4, svg.matches("<rect").count());
+        assert!(svg.contains("<rect x=\"1\" y=\"0\" width=\"1\" height=\"1\" fill=\"#dc2828\"/>"));
+
+        // clear, 2, 2, clear, 3 and end in 3 bit codes
+        assert_eq!(vec![0x94, 0xb8, 0x02], lzw(&[2, 2, 3]));
+        let animation = gif(&[raster.clone(), raster], 10);
+        assert!(animation.starts_with(b"GIF89a\x04\x00\x04\x00"));
+        assert_eq!(Some(&0x3b), animation.last());
+    }
+
+    #[test]
+    fn recording() {
+        // paints white, turns right, paints black, turns right
+        let program = vec![3, 100, 104, 1, 104, 1, 3, 100, 104, 0, 104, 1, 99];
+        let (canvas, steps) = paint_recorded(Amplifier::new(program, vec![]), Color::Unpainted);
+        assert_eq!(2, steps.len());
+        let frames = frames(Color::Unpainted, &steps, 1);
+        assert_eq!(3, frames.len());
+        assert_eq!(vec![3, 0, 0, 0], frames[0].pixels);
+        assert_eq!(vec![2, 3, 0, 0], frames[1].pixels);
+        assert_eq!(Raster::from_canvas(&canvas, 1), frames[2]);
+    }
+}
@@ -0,0 +1,251 @@
+extern crate intcomputer;
+use intcomputer::intcode::Amplifier;
+use std::collections::HashMap;
+use std::fmt;
+
+pub mod export;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Color {
+    Black,
+    White,
+    Unpainted,
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}",
+               match self {
+                   Color::Unpainted => {' '}
+                   Color::White => {'#'}
+                   Color::Black => {'.'}
+               }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}",
+               match self {
+                   Direction::Up => {'^'},
+                   Direction::Down => {'v'},
+                   Direction::Left => {'<'},
+                   Direction::Right => {'>'},
+               }
+        )
+    }
+}
+
+pub fn turn(dir: Direction, val: isize) -> Direction {
+    match (dir, val) {
+        (Direction::Up, 0) => Direction::Left,
+        (Direction::Up, 1) => Direction::Right,
+        (Direction::Down, 0) => Direction::Right,
+        (Direction::Down, 1) => Direction::Left,
+        (Direction::Left, 0) => Direction::Down,
+        (Direction::Left, 1) => Direction::Up,
+        (Direction::Right, 0) => Direction::Up,
+        (Direction::Right, 1) => Direction::Down,
+        (_, _) => panic!("Wrong direction to turn to"),
+    }
+}
+
+pub fn move_forward(dir: Direction, pos: Point) -> Point {
+    match dir {
+        Direction::Up => Point { x: pos.x, y: pos.y - 1 },
+        Direction::Down => Point { x: pos.x, y: pos.y + 1 },
+        Direction::Left => Point { x: pos.x - 1, y: pos.y },
+        Direction::Right => Point { x: pos.x + 1, y: pos.y },
+    }
+}
+
+// Only panels that were painted are stored, so the hull has no edges. The robot is drawn on
+// top of the panels when it is set.
+#[derive(Clone, Debug, Default)]
+pub struct Canvas {
+    panels: HashMap<Point, Color>,
+    pub robot: Option<(Point, Direction)>,
+}
+
+impl Canvas {
+    pub fn new() -> Canvas {
+        Canvas::default()
+    }
+
+    pub fn get(&self, pos: Point) -> Color {
+        *self.panels.get(&pos).unwrap_or(&Color::Unpainted)
+    }
+
+    pub fn paint(&mut self, pos: Point, color: Color) {
+        self.panels.insert(pos, color);
+    }
+
+    pub fn painted_at_least_once(&self) -> usize {
+        self.panels.len()
+    }
+
+    // smallest and largest corner around all panels and the robot
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let robot = self.robot.iter().map(|(pos, _)| pos);
+        let mut points = self.panels.keys().chain(robot);
+        let first = *points.next()?;
+        Some(points.fold((first, first), |(min, max), p| {
+            (Point { x: min.x.min(p.x), y: min.y.min(p.y) }, Point { x: max.x.max(p.x), y: max.y.max(p.y) })
+        }))
+    }
+
+    // white panels as set pixels, cut to the painted letters and without the robot
+    pub fn bitmap(&self) -> Vec<Vec<bool>> {
+        let white: Vec<Point> =
+            self.panels.iter().filter(|(_, color)| **color == Color::White).map(|(pos, _)| *pos).collect();
+        let (min_x, max_x) = match (white.iter().map(|p| p.x).min(), white.iter().map(|p| p.x).max()) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return Vec::new(),
+        };
+        let min_y = white.iter().map(|p| p.y).min().unwrap();
+        let max_y = white.iter().map(|p| p.y).max().unwrap();
+        (min_y..=max_y)
+            .map(|y| (min_x..=max_x).map(|x| self.get(Point { x, y }) == Color::White).collect())
+            .collect()
+    }
+
+    pub fn render(&self) -> String {
+        let (min, max) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return String::new(),
+        };
+        let mut picture = String::new();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let pos = Point { x, y };
+                match self.robot {
+                    Some((robot, dir)) if robot == pos => picture.push_str(&dir.to_string()),
+                    _ => picture.push_str(&self.get(pos).to_string()),
+                }
+            }
+            picture.push('\n');
+        }
+        picture
+    }
+}
+
+// One step of the robot: the panel it painted and where it stands afterwards.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Step {
+    pub pos: Point,
+    pub color: Color,
+    pub robot: (Point, Direction),
+}
+
+// Runs the painting program until it halts, `start` is the color of the first panel.
+pub fn paint(computer: Amplifier, start: Color) -> Canvas {
+    paint_recorded(computer, start).0
+}
+
+// like paint, but also returns every step so the painting can be replayed
+pub fn paint_recorded(mut computer: Amplifier, start: Color) -> (Canvas, Vec<Step>) {
+    let mut canvas = Canvas::new();
+    if start != Color::Unpainted {
+        canvas.paint(Point { x: 0, y: 0 }, start);
+    }
+    let mut steps = Vec::new();
+    let mut pos = Point { x: 0, y: 0 };
+    let mut dir = Direction::Up;
+    loop {
+        computer.push_input(match canvas.get(pos) {
+            Color::Unpainted => 0,
+            Color::Black => 0,
+            Color::White => 1,
+        });
+        let color = match computer.run_program_until_output(false) {
+            Some(result) => result,
+            None => break,
+        };
+        let color = match color {
+            0 => Color::Black,
+            1 => Color::White,
+            _ => panic!("invalid output"),
+        };
+        canvas.paint(pos, color);
+        let painted = pos;
+        match computer.run_program_until_output(false) {
+            Some(output) => {
+                dir = turn(dir, output);
+                pos = move_forward(dir, pos);
+                steps.push(Step { pos: painted, color, robot: (pos, dir) });
+            }
+            None => {
+                steps.push(Step { pos: painted, color, robot: (pos, dir) });
+                break;
+            }
+        }
+    }
+    canvas.robot = Some((pos, dir));
+    (canvas, steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{paint, Canvas, Color, Direction, Point};
+    use intcomputer::intcode::Amplifier;
+
+    // reads the panel color and answers with the given paint and turn for every step
+    fn scripted(steps: &[(isize, isize)]) -> Amplifier {
+        let mut program = Vec::new();
+        for (color, turn) in steps.iter() {
+            program.extend_from_slice(&[3, 1000, 104, *color, 104, *turn]);
+        }
+        program.push(99);
+        Amplifier::new(program, vec![])
+    }
+
+    #[test]
+    fn example() {
+        let steps = [(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)];
+        let canvas = paint(scripted(&steps), Color::Unpainted);
+        assert_eq!(6, canvas.painted_at_least_once());
+        assert_eq!(Some((Point { x: 0, y: -1 }, Direction::Left)), canvas.robot);
+        assert_eq!(" <#\n..#\n## \n", canvas.render());
+    }
+
+    #[test]
+    fn far_away() {
+        // far outside of the old 200 by 80 field
+        let mut canvas = Canvas::new();
+        canvas.paint(Point { x: -500, y: 300 }, Color::White);
+        canvas.paint(Point { x: -498, y: 301 }, Color::Black);
+        canvas.robot = Some((Point { x: -497, y: 300 }, Direction::Left));
+        assert_eq!(Some((Point { x: -500, y: 300 }, Point { x: -497, y: 301 })), canvas.bounds());
+        assert_eq!("#  <\n  . \n", canvas.render());
+        assert_eq!(Color::Unpainted, canvas.get(Point { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn letters() {
+        let mut canvas = Canvas::new();
+        let picture = ocr::parse("#..#.###\n#..#..#.\n####..#.\n#..#..#.\n#..#..#.\n#..#.###");
+        for (y, row) in picture.iter().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                let color = if *pixel { Color::White } else { Color::Black };
+                canvas.paint(Point { x: x as isize - 3, y: y as isize + 2 }, color);
+            }
+        }
+        canvas.robot = Some((Point { x: -10, y: 0 }, Direction::Up));
+        assert_eq!(Ok("HI".to_string()), ocr::decode(&canvas.bitmap()));
+    }
+}
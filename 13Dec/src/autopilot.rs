@@ -0,0 +1,109 @@
+use crate::Tile;
+use intcomputer::intcode::{Amplifier, Event};
+use std::collections::HashSet;
+
+// Plays the game from the tile stream: every time the cabinet asks for the joystick it is
+// tilted towards the ball.
+#[derive(Debug, Default)]
+pub struct Autopilot {
+    pub ball: Option<(isize, isize)>,
+    pub paddle: Option<(isize, isize)>,
+    pub score: isize,
+    // joystick inputs answered so far
+    pub moves: usize,
+    blocks: HashSet<(isize, isize)>,
+}
+
+impl Autopilot {
+    pub fn new() -> Autopilot {
+        Autopilot::default()
+    }
+
+    // one x, y, tile id triple of the output, x = -1 and y = 0 is the score
+    pub fn update(&mut self, x: isize, y: isize, value: isize) {
+        if (x, y) == (-1, 0) {
+            self.score = value;
+            return;
+        }
+        self.blocks.remove(&(x, y));
+        match Tile::new(value) {
+            Tile::Block => {
+                self.blocks.insert((x, y));
+            }
+            Tile::Paddle => self.paddle = Some((x, y)),
+            Tile::Ball => self.ball = Some((x, y)),
+            _ => (),
+        }
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn joystick(&self) -> isize {
+        match (self.ball, self.paddle) {
+            (Some((ball, _)), Some((paddle, _))) => (ball - paddle).signum(),
+            _ => 0,
+        }
+    }
+
+    // Runs the game until it halts. Gives the final score when all blocks were cleared and
+    // None when the ball got away.
+    pub fn play(&mut self, computer: &mut Amplifier) -> Option<isize> {
+        let mut triple = Vec::with_capacity(3);
+        loop {
+            match computer.run_until_event(false) {
+                Ok(Event::Output(value)) => triple.push(value),
+                Ok(Event::NeedInput) => {
+                    computer.push_input(self.joystick());
+                    self.moves += 1;
+                }
+                Ok(Event::Halted) => break,
+                Ok(Event::Watchpoint(_)) => (),
+                Err(fault) => panic!("{}", fault),
+            }
+            if let [x, y, value] = triple[..] {
+                self.update(x, y, value);
+                triple.clear();
+            }
+        }
+        if self.blocks.is_empty() {
+            Some(self.score)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::autopilot::Autopilot;
+    use crate::PROGRAM;
+    use intcomputer::intcode::Amplifier;
+    use intcomputer::patch;
+
+    #[test]
+    fn follows_the_ball() {
+        let mut autopilot = Autopilot::new();
+        assert_eq!(0, autopilot.joystick());
+        autopilot.update(5, 20, 3);
+        autopilot.update(2, 10, 4);
+        autopilot.update(7, 3, 2);
+        autopilot.update(-1, 0, 12);
+        assert_eq!(-1, autopilot.joystick());
+        autopilot.update(2, 10, 0);
+        autopilot.update(9, 11, 4);
+        autopilot.update(7, 3, 0);
+        assert_eq!(1, autopilot.joystick());
+        assert_eq!((0, 12), (autopilot.blocks(), autopilot.score));
+    }
+
+    #[test]
+    fn clears_the_real_game() {
+        let mut computer = Amplifier::from_static(PROGRAM, vec![]);
+        computer.apply_patch(&patch::named("free-play").unwrap()).unwrap();
+        let mut autopilot = Autopilot::new();
+        assert_eq!(Some(11991), autopilot.play(&mut computer));
+        assert_eq!((0, 7240), (autopilot.blocks(), autopilot.moves));
+    }
+}
@@ -1,7 +1,13 @@
 extern crate intcomputer;
 
+use std::env;
 use std::fmt;
 
+mod autopilot;
+mod play;
+
+static PROGRAM: &[i64] = intmacro::include_intcode!("program");
+
 #[derive(Clone, Copy, Eq, PartialEq)]
 enum Tile {
     Empty,
@@ -75,8 +81,7 @@ fn count_block_tiles(screen: &Vec<Vec<Tile>>, x_len: usize, y_len: usize) -> usi
 }
 
 fn part1() {
-    let  program = intcomputer::intcode::read_data("program");
-    let mut computer = intcomputer::intcode::Amplifier::new(program.clone(), vec![]);
+    let mut computer = intcomputer::intcode::Amplifier::from_static(PROGRAM, vec![]);
     const X_LEN: usize = 42;
     const Y_LEN: usize = 23;
     let mut screen = vec![vec![Tile::Empty; X_LEN]; Y_LEN];
@@ -90,27 +95,32 @@ fn part1() {
 }
 
 fn part2() {
-    let mut program = intcomputer::intcode::read_data("program");
-    program[0] = 2;
-    let mut computer = intcomputer::intcode::Amplifier::new(program.clone(), vec![]);
-    const X_LEN: usize = 42;
-    const Y_LEN: usize = 23;
-    let mut screen = vec![vec![Tile::Empty; X_LEN]; Y_LEN];
-
-    while let Some((x, y, tile_id)) = run_3_instructions(&mut computer) {
-        if x == -1 && y == 0 {
-            println!("Score {}", tile_id);
-            print_screen(&screen, X_LEN, Y_LEN);
-        }
-        else {
-            screen[y][x as usize] = Tile::new(tile_id);
-        }
+    let mut computer = intcomputer::intcode::Amplifier::from_static(PROGRAM, vec![]);
+    let free_play = intcomputer::patch::named("free-play").unwrap();
+    computer.apply_patch(&free_play).unwrap_or_else(|e| panic!("{}", e));
+    let mut autopilot = autopilot::Autopilot::new();
+    match autopilot.play(&mut computer) {
+        Some(score) => println!("Score {} after {} moves", score, autopilot.moves),
+        None => println!("Game over with {} blocks left after {} moves", autopilot.blocks(), autopilot.moves),
     }
-    print_screen(&screen, X_LEN, Y_LEN);
-    println!("{}",count_block_tiles(&screen, X_LEN, Y_LEN));
+}
 
+fn play() {
+    let mut computer = intcomputer::intcode::Amplifier::from_static(PROGRAM, vec![]);
+    let free_play = intcomputer::patch::named("free-play").unwrap();
+    computer.apply_patch(&free_play).unwrap_or_else(|e| panic!("{}", e));
+    match play::play(computer) {
+        Ok(score) => println!("Score {}", score),
+        Err(e) => eprintln!("could not set up the terminal: {}", e),
+    }
 }
 
+// `play` starts the game in the terminal instead of solving both parts
 fn main() {
-    part1();
+    if env::args().nth(1).as_deref() == Some("play") {
+        play();
+    } else {
+        part1();
+        part2();
+    }
 }
@@ -0,0 +1,306 @@
+use crate::Tile;
+use intcomputer::intcode::{Amplifier, Event};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::thread;
+use std::time::Duration;
+use termios::{tcsetattr, Termios, ECHO, ICANON, ISIG, TCSANOW, VMIN, VTIME};
+
+// A vm snapshot is mostly padding and takes about 350KB, so one is kept every CHECKPOINT
+// inputs and rewinding replays the inputs after it, up to HISTORY_BYTES of snapshots.
+const CHECKPOINT: usize = 25;
+const HISTORY_BYTES: usize = 32 << 20;
+// inputs undone by one press of the rewind key
+const REWIND: usize = 25;
+// milliseconds between frames, from slow to fast
+const SPEEDS: [u64; 7] = [500, 250, 120, 60, 30, 15, 5];
+
+#[derive(Clone, Default)]
+pub struct Screen {
+    tiles: HashMap<(isize, isize), Tile>,
+    pub score: isize,
+}
+
+impl Screen {
+    pub fn update(&mut self, x: isize, y: isize, value: isize) {
+        if (x, y) == (-1, 0) {
+            self.score = value;
+        } else {
+            self.tiles.insert((x, y), Tile::new(value));
+        }
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.tiles.values().filter(|tile| **tile == Tile::Block).count()
+    }
+
+    pub fn render(&self) -> String {
+        let width = self.tiles.keys().map(|(x, _)| *x + 1).max().unwrap_or(0);
+        let height = self.tiles.keys().map(|(_, y)| *y + 1).max().unwrap_or(0);
+        let mut picture = String::new();
+        for y in 0..height {
+            for x in 0..width {
+                picture.push(match self.tiles.get(&(x, y)) {
+                    Some(Tile::Wall) => '#',
+                    Some(Tile::Block) => '=',
+                    Some(Tile::Paddle) => '-',
+                    Some(Tile::Ball) => 'o',
+                    _ => ' ',
+                });
+            }
+            picture.push('\n');
+        }
+        picture
+    }
+}
+
+// a snapshot and the joystick inputs given since
+struct Checkpoint {
+    computer: Amplifier,
+    screen: Screen,
+    inputs: Vec<isize>,
+}
+
+impl Checkpoint {
+    fn bytes(&self) -> usize {
+        self.computer.memory_size() * mem::size_of::<isize>()
+            + self.screen.tiles.len() * mem::size_of::<((isize, isize), Tile)>()
+    }
+}
+
+// The cabinet between two joystick inputs, with the checkpoints to go back to.
+pub struct Game {
+    computer: Amplifier,
+    pub screen: Screen,
+    history: VecDeque<Checkpoint>,
+    history_bytes: usize,
+    pub over: bool,
+}
+
+impl Game {
+    // runs until the game first asks for the joystick
+    pub fn new(computer: Amplifier) -> Game {
+        let mut game =
+            Game { computer, screen: Screen::default(), history: VecDeque::new(), history_bytes: 0, over: false };
+        game.run();
+        game
+    }
+
+    fn run(&mut self) {
+        let mut triple = Vec::with_capacity(3);
+        loop {
+            match self.computer.run_until_event(false) {
+                Ok(Event::Output(value)) => triple.push(value),
+                Ok(Event::NeedInput) => return,
+                Ok(Event::Halted) => break,
+                Ok(Event::Watchpoint(_)) => (),
+                Err(fault) => panic!("{}", fault),
+            }
+            if let [x, y, value] = triple[..] {
+                self.screen.update(x, y, value);
+                triple.clear();
+            }
+        }
+        self.over = true;
+    }
+
+    pub fn tilt(&mut self, joystick: isize) {
+        if self.over {
+            return;
+        }
+        if self.history.back().is_none_or(|checkpoint| checkpoint.inputs.len() == CHECKPOINT) {
+            let checkpoint =
+                Checkpoint { computer: self.computer.clone(), screen: self.screen.clone(), inputs: Vec::new() };
+            self.history_bytes += checkpoint.bytes();
+            self.history.push_back(checkpoint);
+            while self.history_bytes > HISTORY_BYTES && self.history.len() > 1 {
+                self.history_bytes -= self.history.pop_front().unwrap().bytes();
+            }
+        }
+        self.history.back_mut().unwrap().inputs.push(joystick);
+        self.computer.push_input(joystick);
+        self.run();
+    }
+
+    // goes back up to `inputs` joystick inputs, false when there is nothing to go back to
+    pub fn rewind(&mut self, inputs: usize) -> bool {
+        if self.history.is_empty() {
+            return false;
+        }
+        let mut inputs = inputs.max(1);
+        loop {
+            let oldest = self.history.len() == 1;
+            let last = self.history.back_mut().unwrap();
+            if inputs <= last.inputs.len() {
+                last.inputs.truncate(last.inputs.len() - inputs);
+                break;
+            }
+            inputs -= last.inputs.len();
+            if oldest {
+                last.inputs.clear();
+                break;
+            }
+            self.history_bytes -= self.history.pop_back().unwrap().bytes();
+        }
+
+        let last = self.history.back().unwrap();
+        self.computer = last.computer.clone();
+        self.screen = last.screen.clone();
+        self.over = false;
+        for joystick in last.inputs.clone() {
+            self.computer.push_input(joystick);
+            self.run();
+        }
+        true
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Key {
+    Left,
+    Right,
+    Rewind,
+    Faster,
+    Slower,
+    Quit,
+}
+
+// arrow keys arrive as escape sequences, unknown bytes are skipped
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match &bytes[i..] {
+            [0x1b, b'[', b'D', ..] => {
+                keys.push(Key::Left);
+                i += 2;
+            }
+            [0x1b, b'[', b'C', ..] => {
+                keys.push(Key::Right);
+                i += 2;
+            }
+            [b'r', ..] | [b'R', ..] => keys.push(Key::Rewind),
+            [b'+', ..] | [b'=', ..] => keys.push(Key::Faster),
+            [b'-', ..] => keys.push(Key::Slower),
+            [b'q', ..] | [b'Q', ..] | [3, ..] => keys.push(Key::Quit),
+            _ => (),
+        }
+        i += 1;
+    }
+    keys
+}
+
+// Puts stdin into non-blocking mode without echo and restores it when dropped.
+struct RawMode {
+    saved: Termios,
+}
+
+impl RawMode {
+    fn enter() -> io::Result<RawMode> {
+        let saved = Termios::from_fd(0)?;
+        let mut raw = saved;
+        // without ISIG Ctrl-C arrives as a key and quits through the drop below
+        raw.c_lflag &= !(ICANON | ECHO | ISIG);
+        raw.c_cc[VMIN] = 0;
+        raw.c_cc[VTIME] = 0;
+        tcsetattr(0, TCSANOW, &raw)?;
+        print!("\x1b[?25l\x1b[2J");
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = tcsetattr(0, TCSANOW, &self.saved);
+        print!("\x1b[?25h");
+        let _ = io::stdout().flush();
+    }
+}
+
+// Plays the game in the terminal. Frames without an arrow key leave the joystick in the
+// middle, holding one down moves the paddle through key repeat.
+pub fn play(computer: Amplifier) -> io::Result<isize> {
+    let _raw = RawMode::enter()?;
+    let mut game = Game::new(computer);
+    let mut speed = 2;
+    let mut buffer = [0; 64];
+    loop {
+        print!("\x1b[H{}", game.screen.render());
+        print!(
+            "\x1b[KScore {}  Blocks {}  Speed {}/{}\n\x1b[K{}\n",
+            game.screen.score,
+            game.screen.blocks(),
+            speed + 1,
+            SPEEDS.len(),
+            if game.over { "game over, r rewinds, q quits" } else { "left/right move, r rewind, +/- speed, q quit" }
+        );
+        io::stdout().flush()?;
+        thread::sleep(Duration::from_millis(SPEEDS[speed]));
+
+        let read = io::stdin().read(&mut buffer)?;
+        let mut joystick = 0;
+        for key in parse_keys(&buffer[..read]) {
+            match key {
+                Key::Left => joystick = -1,
+                Key::Right => joystick = 1,
+                Key::Rewind => {
+                    game.rewind(REWIND);
+                }
+                Key::Faster => speed = (speed + 1).min(SPEEDS.len() - 1),
+                Key::Slower => speed = speed.saturating_sub(1),
+                Key::Quit => return Ok(game.screen.score),
+            }
+        }
+        game.tilt(joystick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::play::{parse_keys, Game, Key, CHECKPOINT};
+    use crate::{Tile, PROGRAM};
+    use intcomputer::intcode::Amplifier;
+    use intcomputer::patch;
+
+    #[test]
+    fn keys() {
+        let keys = parse_keys(b"\x1b[D\x1b[Cx+-rq\x1b[A");
+        assert_eq!(vec![Key::Left, Key::Right, Key::Faster, Key::Slower, Key::Rewind, Key::Quit], keys);
+        assert_eq!(vec![Key::Quit], parse_keys(b"\x03"));
+    }
+
+    #[test]
+    fn rewind() {
+        let mut computer = Amplifier::from_static(PROGRAM, vec![]);
+        computer.apply_patch(&patch::named("free-play").unwrap()).unwrap();
+        let mut game = Game::new(computer);
+        let blocks = game.screen.blocks();
+        assert!(blocks > 0);
+        let start = game.screen.render();
+        assert!(!game.rewind(1));
+
+        // follows the ball for a few checkpoints, then standing still loses it
+        let x = |game: &Game, tile| game.screen.tiles.iter().find(|(_, t)| **t == tile).map(|((x, _), _)| *x).unwrap();
+        let mut frames = Vec::new();
+        for _ in 0..60 {
+            game.tilt((x(&game, Tile::Ball) - x(&game, Tile::Paddle)).signum());
+            frames.push(game.screen.render());
+        }
+        assert_eq!(3, game.history.len());
+        assert!(game.rewind(3));
+        assert_eq!(frames[56], game.screen.render());
+        assert!(game.rewind(CHECKPOINT));
+        assert_eq!(frames[31], game.screen.render());
+        while !game.over {
+            game.tilt(0);
+        }
+        assert!(game.screen.render() != start);
+        assert!(game.rewind(usize::MAX));
+        assert!(!game.over);
+        assert_eq!(start, game.screen.render());
+        assert_eq!(blocks, game.screen.blocks());
+        game.tilt(1);
+        assert!(game.screen.render() != start);
+    }
+}
@@ -1,5 +1,6 @@
 use intcomputer::intcode;
 use intcomputer::intcode::Amplifier;
+use intmacro::include_intcode;
 use std::collections::{HashMap, VecDeque};
 use petgraph::graphmap::UnGraphMap;
 use petgraph::dot::{Config, Dot};
@@ -160,8 +161,7 @@ fn fill_with_oxygen(graph: &UnGraphMap<Point, u8>, pos: Point, visited: &mut Has
 }
 
 fn main() {
-    let program = intcode::read_data("program");
-    let mut computer = intcode::Amplifier::new(program, vec![]);
+    let mut computer = intcode::Amplifier::from_static(include_intcode!("program"), vec![]);
 
     let mut field: HashMap<Point, char> = HashMap::new();
     field.insert(Point { x: 0, y: 0 }, '.');
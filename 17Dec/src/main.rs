@@ -1,5 +1,7 @@
 use intcomputer::intcode;
 use intcomputer::intcode::Amplifier;
+use intcomputer::terminal::Terminal;
+use intmacro::include_intcode;
 
 #[allow(dead_code)]
 fn printfield(field: &Vec<Vec<u8>>) {
@@ -51,28 +53,15 @@ fn calculate_sum_of_alignment_parameters(intersections: Vec<Point>) -> usize {
 }
 
 fn read_field(mut computer: Amplifier) -> Vec<Vec<u8>> {
-    let mut field = vec![vec![0;0];0];
-    assert_eq!(0, field.len());
-    field.push(vec![]);
-    assert_eq!(1, field.len());
-    let mut y = 0;
+    let mut terminal = Terminal::new(100);
     while let Some(c) = computer.run_program_until_output(false) {
-        match (c as u8) as char {
-            '\n' => {
-                y += 1;
-                field.push(vec![])
-            },
-            _ => field[y].push(c as u8),
-        }
+        terminal.write(c);
     }
-    field.remove(y);
-    field.remove(y - 1);
-    field
+    terminal.frames()[0].iter().map(|line| line.bytes().collect()).collect()
 }
 
 fn main() {
-    let program = intcode::read_data("program");
-    let computer = intcode::Amplifier::new(program, vec![]);
+    let computer = intcode::Amplifier::from_static(include_intcode!("program"), vec![]);
     let field = read_field(computer);
 
 //    printfield(&field);
@@ -0,0 +1,3 @@
+fn main() {
+    intcomputer::translate::build("program", "beam");
+}
@@ -1,10 +1,10 @@
-use intcomputer::intcode;
+include!(concat!(env!("OUT_DIR"), "/beam.rs"));
 
 const X_LEN: usize = 100;
 const Y_LEN: usize = 100;
 
 
-fn map_area(mut computer: &intcode::Amplifier) -> [[bool;Y_LEN];X_LEN] {
+fn map_area(computer: &beam::Amplifier) -> [[bool;Y_LEN];X_LEN] {
 
     let mut area = [[false; Y_LEN]; X_LEN];
     for y in 0..Y_LEN {
@@ -38,9 +38,26 @@ fn count_affected_points(area: &[[bool;Y_LEN];X_LEN]) -> usize {
 }
 
 fn main() {
-    let program = intcode::read_data("program");
-    let computer = intcode::Amplifier::new(program, vec![]);
+    let computer = beam::Amplifier::new(vec![]);
     let area = map_area(&computer);
     print_area(&area);
     println!("{}", count_affected_points(&area));
 }
+
+#[cfg(test)]
+mod tests {
+    use intcomputer::intcode;
+
+    #[test]
+    fn translated_program_matches_interpreter() {
+        let program = intcode::read_data("program");
+        for y in 0..20 {
+            for x in 0..20 {
+                let mut interpreted = intcode::Amplifier::new(program.clone(), vec![x, y]);
+                let mut translated = crate::beam::Amplifier::new(vec![x, y]);
+                assert_eq!(interpreted.run_program_until_output(false),
+                           translated.run_program_until_output(false));
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use intcomputer::intcode;
+use intmacro::include_intcode;
 use std::collections::VecDeque;
 use std::fs;
 use std::convert::TryInto;
@@ -25,9 +26,9 @@ fn print_output(computer: &mut intcode::Amplifier) {
 }
 
 fn part1() {
-    let program = intcode::read_data("program");
+    let program = include_intcode!("program");
     let script = read_spring_script("script");
-    let mut computer = intcode::Amplifier::new(program, vec![]);
+    let mut computer = intcode::Amplifier::from_static(program, vec![]);
     computer.push_input_vec(script);
     print_output(&mut computer);
 }
@@ -57,15 +57,25 @@ fn get_magic_number(layer: Vec<Vec<u8>>) -> usize {
     num_of_ones * num_of_twos
 }
 
-fn draw_picture(layers: Vec<Vec<Vec<u8>>>) {
+// the first pixel that is not transparent, true for white
+fn decode_image(layers: &[Vec<Vec<u8>>]) -> Vec<Vec<bool>> {
+    let mut image = vec![vec![false; X_LEN]; Y_LEN];
     for y in 0..Y_LEN {
         for x in 0..X_LEN {
             let mut layer_index = 0;
             while layers[layer_index][y][x] == 2 {
                 layer_index += 1;
             }
-            let pixel = layers[layer_index][y][x];
-            print!("{}", if pixel == 0 {"█"} else {" "});
+            image[y][x] = layers[layer_index][y][x] == 1;
+        }
+    }
+    image
+}
+
+fn draw_picture(image: &[Vec<bool>]) {
+    for row in image.iter() {
+        for pixel in row.iter() {
+            print!("{}", if *pixel {" "} else {"█"});
         }
         println!();
     }
@@ -75,5 +85,10 @@ fn main() {
     let digits = read_data();
     let (layers, index) = get_layers(digits);
     println!("{}", get_magic_number(layers[index].clone()));
-    draw_picture(layers);
+    let image = decode_image(&layers);
+    draw_picture(&image);
+    match ocr::decode(&image) {
+        Ok(text) => println!("{}", text),
+        Err(error) => println!("{}", error),
+    }
 }
\ No newline at end of file
@@ -0,0 +1,18 @@
+use std::env;
+use std::path::Path;
+
+// Generates intcomputer.h from src/lib.rs into OUT_DIR, tests/capi.rs checks include/ against it.
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let config = cbindgen::Config::from_file(Path::new(&crate_dir).join("cbindgen.toml"))
+        .expect("unable to read cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(Path::new(&crate_dir).join("src/lib.rs"))
+        .generate()
+        .expect("unable to generate the C header")
+        .write_to_file(Path::new(&out_dir).join("intcomputer.h"));
+}
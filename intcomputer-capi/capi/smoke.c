@@ -0,0 +1,51 @@
+/* Runs day 9 part 1 through the C interface.
+   cc capi/smoke.c -Iinclude -Ltarget/debug -lintcomputer_capi -o smoke && LD_LIBRARY_PATH=target/debug ./smoke ../intcomputer/9 */
+#include <stdio.h>
+#include "intcomputer.h"
+
+static int64_t program[4096];
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "usage: smoke <program>\n");
+        return 2;
+    }
+    FILE *file = fopen(argv[1], "r");
+    if (!file) {
+        perror(argv[1]);
+        return 1;
+    }
+    size_t len = 0;
+    long long value;
+    while (len < sizeof program / sizeof *program && fscanf(file, "%lld,", &value) == 1) {
+        program[len++] = value;
+    }
+    fclose(file);
+
+    IntcodeVm *vm = intcode_create(program, len);
+    if (!vm) {
+        fprintf(stderr, "empty program\n");
+        return 1;
+    }
+    int event;
+    while ((event = intcode_run(vm)) != INTCODE_HALTED) {
+        if (event == INTCODE_NEED_INPUT) {
+            intcode_push_input(vm, 1);
+        } else if (event == INTCODE_OUTPUT) {
+            printf("%lld\n", (long long)intcode_output(vm));
+        } else {
+            char message[128];
+            intcode_fault(vm, message, sizeof message);
+            fprintf(stderr, "fault: %s\n", message);
+            intcode_free(vm);
+            return 1;
+        }
+    }
+
+    IntcodeVm *copy = intcode_snapshot(vm);
+    int halted = intcode_run(copy) == INTCODE_HALTED;
+    printf("%zu words, snapshot %s\n", intcode_memory(vm, NULL, 0), halted ? "halted" : "running");
+    intcode_free(copy);
+    intcode_free(vm);
+    return 0;
+}
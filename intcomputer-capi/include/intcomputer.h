@@ -0,0 +1,96 @@
+#ifndef INTCOMPUTER_H
+#define INTCOMPUTER_H
+
+/* Generated by build.rs from src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define INTCODE_OUTPUT 0
+
+#define INTCODE_NEED_INPUT 1
+
+#define INTCODE_HALTED 2
+
+#define INTCODE_FAULT 3
+
+#define INTCODE_PAUSED 4
+
+typedef struct IntcodeVm IntcodeVm;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Creates a vm from `len` words, returns NULL if the program is empty.
+
+ # Safety
+ `program` must point to `len` readable words.
+ */
+struct IntcodeVm *intcode_create(const int64_t *program, size_t len);
+
+/*
+ Copies the complete state, the copy has to be freed on its own.
+
+ # Safety
+ `vm` must be NULL or returned by intcode_create or intcode_snapshot.
+ */
+struct IntcodeVm *intcode_snapshot(const struct IntcodeVm *vm);
+
+/*
+ # Safety
+ `vm` must be NULL or returned by intcode_create or intcode_snapshot, it is invalid afterwards.
+ */
+void intcode_free(struct IntcodeVm *vm);
+
+/*
+ # Safety
+ `vm` must be NULL or returned by intcode_create or intcode_snapshot.
+ */
+void intcode_push_input(struct IntcodeVm *vm, int64_t value);
+
+/*
+ Runs until the next output, an empty input buffer, a halt, a fault or a pausing watchpoint.
+ A faulted vm keeps returning INTCODE_FAULT.
+
+ # Safety
+ `vm` must be NULL or returned by intcode_create or intcode_snapshot.
+ */
+int32_t intcode_run(struct IntcodeVm *vm);
+
+/*
+ The value of the last INTCODE_OUTPUT event.
+
+ # Safety
+ `vm` must be NULL or returned by intcode_create or intcode_snapshot.
+ */
+int64_t intcode_output(const struct IntcodeVm *vm);
+
+/*
+ Copies up to `capacity` words of memory into `buffer` and returns the size of the memory.
+
+ # Safety
+ `vm` must be NULL or returned by intcode_create or intcode_snapshot,
+ `buffer` must be NULL or have room for `capacity` words.
+ */
+size_t intcode_memory(const struct IntcodeVm *vm, int64_t *buffer, size_t capacity);
+
+/*
+ Copies the NUL terminated fault message into `buffer`, truncating it to `capacity` bytes.
+ Returns the length of the message without the NUL, 0 if the vm did not fault.
+
+ # Safety
+ `vm` must be NULL or returned by intcode_create or intcode_snapshot,
+ `buffer` must be NULL or have room for `capacity` bytes.
+ */
+size_t intcode_fault(const struct IntcodeVm *vm, char *buffer, size_t capacity);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* INTCOMPUTER_H */
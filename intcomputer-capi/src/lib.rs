@@ -0,0 +1,199 @@
+// C interface, see include/intcomputer.h (checked against the one build.rs generates) and capi/smoke.c.
+// Words are 64 bit on the C side, every function accepts NULL for the vm and does nothing then.
+use intcomputer::intcode::{Amplifier, Event};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+pub const INTCODE_OUTPUT: i32 = 0;
+pub const INTCODE_NEED_INPUT: i32 = 1;
+pub const INTCODE_HALTED: i32 = 2;
+pub const INTCODE_FAULT: i32 = 3;
+pub const INTCODE_PAUSED: i32 = 4;
+
+pub struct IntcodeVm {
+    amp: Amplifier,
+    output: i64,
+    fault: Option<String>,
+}
+
+/// Creates a vm from `len` words, returns NULL if the program is empty.
+///
+/// # Safety
+/// `program` must point to `len` readable words.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_create(program: *const i64, len: usize) -> *mut IntcodeVm {
+    if program.is_null() || len == 0 {
+        return ptr::null_mut();
+    }
+    let program = slice::from_raw_parts(program, len).iter().map(|v| *v as isize).collect();
+    let vm = IntcodeVm { amp: Amplifier::new(program, vec![]), output: 0, fault: None };
+    Box::into_raw(Box::new(vm))
+}
+
+/// Copies the complete state, the copy has to be freed on its own.
+///
+/// # Safety
+/// `vm` must be NULL or returned by intcode_create or intcode_snapshot.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_snapshot(vm: *const IntcodeVm) -> *mut IntcodeVm {
+    match vm.as_ref() {
+        Some(vm) => Box::into_raw(Box::new(IntcodeVm { amp: vm.amp.clone(), output: vm.output, fault: vm.fault.clone() })),
+        None => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `vm` must be NULL or returned by intcode_create or intcode_snapshot, it is invalid afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_free(vm: *mut IntcodeVm) {
+    if !vm.is_null() {
+        drop(Box::from_raw(vm));
+    }
+}
+
+/// # Safety
+/// `vm` must be NULL or returned by intcode_create or intcode_snapshot.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_push_input(vm: *mut IntcodeVm, value: i64) {
+    if let Some(vm) = vm.as_mut() {
+        vm.amp.push_input(value as isize);
+    }
+}
+
+/// Runs until the next output, an empty input buffer, a halt, a fault or a pausing watchpoint.
+/// A faulted vm keeps returning INTCODE_FAULT.
+///
+/// # Safety
+/// `vm` must be NULL or returned by intcode_create or intcode_snapshot.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_run(vm: *mut IntcodeVm) -> i32 {
+    let vm = match vm.as_mut() {
+        Some(vm) => vm,
+        None => return INTCODE_FAULT,
+    };
+    if vm.fault.is_some() {
+        return INTCODE_FAULT;
+    }
+    // no panic may unwind into C
+    let amp = &mut vm.amp;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| amp.run_until_event(false)));
+    match result {
+        Ok(Ok(Event::Output(value))) => {
+            vm.output = value as i64;
+            INTCODE_OUTPUT
+        }
+        Ok(Ok(Event::NeedInput)) => INTCODE_NEED_INPUT,
+        Ok(Ok(Event::Halted)) => INTCODE_HALTED,
+        Ok(Ok(Event::Watchpoint(_))) => INTCODE_PAUSED,
+        Ok(Err(fault)) => {
+            vm.fault = Some(fault.to_string());
+            INTCODE_FAULT
+        }
+        Err(_) => {
+            vm.fault = Some("panic in the vm".to_string());
+            INTCODE_FAULT
+        }
+    }
+}
+
+/// The value of the last INTCODE_OUTPUT event.
+///
+/// # Safety
+/// `vm` must be NULL or returned by intcode_create or intcode_snapshot.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_output(vm: *const IntcodeVm) -> i64 {
+    vm.as_ref().map_or(0, |vm| vm.output)
+}
+
+/// Copies up to `capacity` words of memory into `buffer` and returns the size of the memory.
+///
+/// # Safety
+/// `vm` must be NULL or returned by intcode_create or intcode_snapshot,
+/// `buffer` must be NULL or have room for `capacity` words.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_memory(vm: *const IntcodeVm, buffer: *mut i64, capacity: usize) -> usize {
+    let vm = match vm.as_ref() {
+        Some(vm) => vm,
+        None => return 0,
+    };
+    let memory = vm.amp.get_program_clone();
+    if !buffer.is_null() {
+        let buffer = slice::from_raw_parts_mut(buffer, capacity);
+        for (word, value) in buffer.iter_mut().zip(memory.iter()) {
+            *word = *value as i64;
+        }
+    }
+    memory.len()
+}
+
+/// Copies the NUL terminated fault message into `buffer`, truncating it to `capacity` bytes.
+/// Returns the length of the message without the NUL, 0 if the vm did not fault.
+///
+/// # Safety
+/// `vm` must be NULL or returned by intcode_create or intcode_snapshot,
+/// `buffer` must be NULL or have room for `capacity` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_fault(vm: *const IntcodeVm, buffer: *mut c_char, capacity: usize) -> usize {
+    let message = match vm.as_ref().and_then(|vm| vm.fault.as_ref()) {
+        Some(message) => message.as_bytes(),
+        None => return 0,
+    };
+    if !buffer.is_null() && capacity > 0 {
+        let len = message.len().min(capacity - 1);
+        ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, buffer, len);
+        *buffer.add(len) = 0;
+    }
+    message.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::ptr;
+
+    #[test]
+    fn run_and_snapshot() {
+        // echoes its input doubled until it reads 0
+        let program = [3, 20, 1006, 20, 14, 1002, 20, 2, 20, 4, 20, 1105, 1, 0, 99];
+        unsafe {
+            let vm = intcode_create(program.as_ptr(), program.len());
+            assert_eq!(INTCODE_NEED_INPUT, intcode_run(vm));
+            intcode_push_input(vm, 21);
+            assert_eq!(INTCODE_OUTPUT, intcode_run(vm));
+            assert_eq!(42, intcode_output(vm));
+
+            let copy = intcode_snapshot(vm);
+            intcode_push_input(vm, 0);
+            assert_eq!(INTCODE_HALTED, intcode_run(vm));
+            intcode_push_input(copy, 5);
+            assert_eq!(INTCODE_OUTPUT, intcode_run(copy));
+            assert_eq!(10, intcode_output(copy));
+
+            let mut memory = [0; 21];
+            assert!(intcode_memory(copy, memory.as_mut_ptr(), memory.len()) > 21);
+            assert_eq!(10, memory[20]);
+            intcode_free(copy);
+            intcode_free(vm);
+        }
+    }
+
+    #[test]
+    fn faults_and_null() {
+        unsafe {
+            assert!(intcode_create(ptr::null(), 3).is_null());
+            assert_eq!(INTCODE_FAULT, intcode_run(ptr::null_mut()));
+            intcode_free(ptr::null_mut());
+
+            let program = [12i64, 0, 0, 0];
+            let vm = intcode_create(program.as_ptr(), program.len());
+            assert_eq!(INTCODE_FAULT, intcode_run(vm));
+            let mut message = [0 as std::os::raw::c_char; 8];
+            let len = intcode_fault(vm, message.as_mut_ptr(), message.len());
+            assert_eq!("Illegal Opcode 12 at 0".len(), len);
+            assert_eq!(0, message[7]);
+            intcode_free(vm);
+        }
+    }
+}
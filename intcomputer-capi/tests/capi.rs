@@ -0,0 +1,41 @@
+// Builds capi/smoke.c against the shared library and runs it on day 9 part 1.
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn c_smoke_test() {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // target/<profile>/deps/capi-<hash>, the library sits in target/<profile>
+    let exe = env::current_exe().unwrap();
+    let profile_dir = exe.parent().unwrap().parent().unwrap().to_path_buf();
+    assert!(profile_dir.join("libintcomputer_capi.so").exists(), "cdylib missing in {:?}", profile_dir);
+
+    // the build only writes the header into OUT_DIR, the copy in include/ is for C users
+    let generated = PathBuf::from(env!("OUT_DIR")).join("intcomputer.h");
+    let checked_in = fs::read_to_string(manifest.join("include/intcomputer.h")).unwrap();
+    assert_eq!(fs::read_to_string(&generated).unwrap(), checked_in,
+               "include/intcomputer.h is out of date, copy {:?} over it", generated);
+
+    let smoke = profile_dir.join("capi-smoke");
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(manifest.join("capi/smoke.c"))
+        .arg("-I").arg(generated.parent().unwrap())
+        .arg("-L").arg(&profile_dir)
+        .arg("-lintcomputer_capi")
+        .arg("-o").arg(&smoke)
+        .status()
+        .expect("C compiler not found");
+    assert!(status.success());
+
+    let output = Command::new(&smoke)
+        .arg(manifest.join("../intcomputer/9"))
+        .env("LD_LIBRARY_PATH", &profile_dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!("3497884671\n41933 words, snapshot halted\n", String::from_utf8_lossy(&output.stdout));
+}
@@ -0,0 +1,323 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}",
+            match self {
+                Severity::Info => "info",
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    IllegalOpcode(isize),
+    // not decodable as it is, but written to by reachable code before it runs
+    Overwritten(isize),
+    InvalidMode(isize),
+    ImmediateWrite(usize),
+    NegativeAddress(isize),
+    SelfModification(usize),
+    ComputedJump,
+    ReadBeyondImage(usize),
+    RunsOffImage,
+}
+
+impl Kind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Kind::IllegalOpcode(_) => Severity::Error,
+            Kind::Overwritten(_) => Severity::Warning,
+            Kind::InvalidMode(_) => Severity::Error,
+            Kind::ImmediateWrite(_) => Severity::Error,
+            Kind::NegativeAddress(_) => Severity::Error,
+            Kind::SelfModification(_) => Severity::Warning,
+            Kind::ComputedJump => Severity::Info,
+            Kind::ReadBeyondImage(_) => Severity::Warning,
+            Kind::RunsOffImage => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kind::IllegalOpcode(op) => write!(f, "illegal opcode {}", op),
+            Kind::Overwritten(word) => write!(f, "{} is overwritten before it runs, not checked", word),
+            Kind::InvalidMode(word) => write!(f, "invalid parameter mode in {}", word),
+            Kind::ImmediateWrite(param) => write!(f, "parameter {} is written to but in immediate mode", param),
+            Kind::NegativeAddress(addr) => write!(f, "access to negative address {}", addr),
+            Kind::SelfModification(target) => write!(f, "write into code at {}", target),
+            Kind::ComputedJump => write!(f, "jump to computed target"),
+            Kind::ReadBeyondImage(target) => write!(f, "read of {} beyond the loaded image", target),
+            Kind::RunsOffImage => write!(f, "execution runs past the end of the image"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub address: usize,
+    pub kind: Kind,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>6}: {}: {}", self.address, self.severity(), self.kind)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Param {
+    Read,
+    Write,
+    Target,
+}
+
+// Parameter layout of every instruction the Amplifier understands.
+pub fn params(opcode: isize) -> Option<&'static [Param]> {
+    match opcode {
+        1 | 2 | 7 | 8 => Some(&[Param::Read, Param::Read, Param::Write]),
+        3 => Some(&[Param::Write]),
+        4 | 9 => Some(&[Param::Read]),
+        5 | 6 => Some(&[Param::Read, Param::Target]),
+        99 => Some(&[]),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    pub address: usize,
+    pub opcode: isize,
+    pub modes: [isize; 3],
+    pub params: Vec<isize>,
+}
+
+impl Decoded {
+    // words taken by the instruction, opcode included
+    pub fn width(&self) -> usize {
+        1 + self.params.len()
+    }
+}
+
+pub fn decode(program: &[isize], address: usize) -> Result<Decoded, Kind> {
+    let word = match program.get(address) {
+        Some(word) => *word,
+        None => return Err(Kind::RunsOffImage),
+    };
+    if word < 0 {
+        return Err(Kind::IllegalOpcode(word));
+    }
+    let opcode = word % 100;
+    let layout = match params(opcode) {
+        Some(layout) => layout,
+        None => return Err(Kind::IllegalOpcode(opcode)),
+    };
+    let mut modes = [0; 3];
+    let mut rest = word / 100;
+    for mode in modes.iter_mut() {
+        *mode = rest % 10;
+        if *mode > 2 {
+            return Err(Kind::InvalidMode(word));
+        }
+        rest /= 10;
+    }
+    if rest != 0 {
+        return Err(Kind::InvalidMode(word));
+    }
+    let mut params = Vec::new();
+    for i in 0..layout.len() {
+        match program.get(address + 1 + i) {
+            Some(param) => params.push(*param),
+            None => return Err(Kind::RunsOffImage),
+        }
+    }
+    Ok(Decoded { address, opcode, modes, params })
+}
+
+pub struct Analysis {
+    pub reachable: Vec<Decoded>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub fn analyze(program: &[isize]) -> Analysis {
+    analyze_from(program, 0)
+}
+
+pub fn analyze_from(program: &[isize], entry: usize) -> Analysis {
+    let mut diagnostics = Vec::new();
+    let mut reachable = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut worklist = VecDeque::new();
+    let mut undecodable = Vec::new();
+    worklist.push_back(entry);
+
+    while let Some(ip) = worklist.pop_front() {
+        if !visited.insert(ip) {
+            continue;
+        }
+        let inst = match decode(program, ip) {
+            Ok(inst) => inst,
+            Err(kind) => {
+                undecodable.push(Diagnostic { address: ip, kind });
+                continue;
+            }
+        };
+        let layout = params(inst.opcode).unwrap();
+        let mut falls_through = inst.opcode != 99;
+        for (i, param) in layout.iter().enumerate() {
+            let mode = inst.modes[i];
+            let value = inst.params[i];
+            if *param == Param::Write && mode == 1 {
+                diagnostics.push(Diagnostic { address: ip, kind: Kind::ImmediateWrite(i + 1) });
+            }
+            if mode == 0 && value < 0 {
+                diagnostics.push(Diagnostic { address: ip, kind: Kind::NegativeAddress(value) });
+            }
+            if mode == 0 && *param != Param::Write && value >= 0 && value as usize >= program.len() {
+                diagnostics.push(Diagnostic { address: ip, kind: Kind::ReadBeyondImage(value as usize) });
+            }
+            if *param == Param::Target {
+                let condition = if inst.modes[0] == 1 { Some(inst.params[0] != 0) } else { None };
+                let jumps = match (inst.opcode, condition) {
+                    (5, Some(c)) => Some(c),
+                    (6, Some(c)) => Some(!c),
+                    _ => None,
+                };
+                if jumps == Some(true) {
+                    falls_through = false;
+                }
+                if jumps == Some(false) {
+                    continue;
+                }
+                if mode != 1 {
+                    diagnostics.push(Diagnostic { address: ip, kind: Kind::ComputedJump });
+                } else if value < 0 {
+                    diagnostics.push(Diagnostic { address: ip, kind: Kind::NegativeAddress(value) });
+                } else {
+                    worklist.push_back(value as usize);
+                }
+            }
+        }
+        if falls_through {
+            worklist.push_back(ip + inst.width());
+        }
+        reachable.push(inst);
+    }
+
+    let mut code = visited;
+    for inst in reachable.iter() {
+        code.extend(inst.address..inst.address + inst.width());
+    }
+    let mut written = BTreeSet::new();
+    for inst in reachable.iter() {
+        let layout = params(inst.opcode).unwrap();
+        for (i, param) in layout.iter().enumerate() {
+            let value = inst.params[i];
+            if *param == Param::Write && inst.modes[i] == 0 && value >= 0 {
+                written.insert(value as usize);
+                if code.contains(&(value as usize)) {
+                    let kind = Kind::SelfModification(value as usize);
+                    diagnostics.push(Diagnostic { address: inst.address, kind });
+                }
+            }
+        }
+    }
+    // day 5 stores its first instructions before running them
+    for mut diagnostic in undecodable.into_iter() {
+        if written.contains(&diagnostic.address) {
+            diagnostic.kind = Kind::Overwritten(program[diagnostic.address]);
+        }
+        diagnostics.push(diagnostic);
+    }
+
+    reachable.sort_by_key(|inst| inst.address);
+    diagnostics.sort_by_key(|d| d.address);
+    Analysis { reachable, diagnostics }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::{analyze, Kind, Severity};
+
+    fn kinds(program: &[isize]) -> Vec<Kind> {
+        analyze(program).diagnostics.into_iter().map(|d| d.kind).collect()
+    }
+
+    #[test]
+    fn clean_program() {
+        assert_eq!(Vec::<Kind>::new(), kinds(&[1, 9, 10, 11, 4, 11, 99, 0, 0, 3, 4, 0]));
+    }
+
+    #[test]
+    fn illegal_opcode_after_jump() {
+        let analysis = analyze(&[1105, 1, 4, 99, 42]);
+        assert_eq!(1, analysis.diagnostics.len());
+        assert_eq!(4, analysis.diagnostics[0].address);
+        assert_eq!(Kind::IllegalOpcode(42), analysis.diagnostics[0].kind);
+        assert_eq!(Severity::Error, analysis.diagnostics[0].severity());
+    }
+
+    #[test]
+    fn written_before_it_runs() {
+        // stores 99 over the 0 at 4 before running it
+        assert_eq!(vec![Kind::SelfModification(4), Kind::Overwritten(0)], kinds(&[1101, 90, 9, 4, 0]));
+        let day5 = analyze(&crate::intcode::read_data("5"));
+        assert!(day5.diagnostics.iter().all(|d| d.severity() < Severity::Error));
+    }
+
+    #[test]
+    fn unreachable_garbage_is_ignored() {
+        assert_eq!(Vec::<Kind>::new(), kinds(&[1105, 1, 4, 42, 99]));
+    }
+
+    #[test]
+    fn immediate_write() {
+        assert_eq!(vec![Kind::ImmediateWrite(3)], kinds(&[11101, 1, 1, 5, 99, 0]));
+    }
+
+    #[test]
+    fn negative_address() {
+        assert_eq!(vec![Kind::NegativeAddress(-3)], kinds(&[4, -3, 99]));
+    }
+
+    #[test]
+    fn self_modification() {
+        assert_eq!(vec![Kind::SelfModification(6)], kinds(&[1101, 1, 98, 6, 1105, 1, 0]));
+    }
+
+    #[test]
+    fn computed_jump_and_read_beyond_image() {
+        assert_eq!(vec![Kind::ReadBeyondImage(100), Kind::ComputedJump], kinds(&[5, 100, 0, 99]));
+    }
+
+    #[test]
+    fn runs_off_image() {
+        assert_eq!(vec![Kind::RunsOffImage], kinds(&[1101, 1, 1, 5]));
+    }
+
+    #[test]
+    fn day9_reachable_code_is_clean() {
+        let program = crate::intcode::read_data("9");
+        let analysis = analyze(&program);
+        assert!(analysis.diagnostics.iter().all(|d| d.severity() < Severity::Error));
+    }
+}
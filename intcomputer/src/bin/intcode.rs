@@ -0,0 +1,76 @@
+use intcomputer::intcode::Amplifier;
+use intcomputer::image;
+use intcomputer::loader::{self, Loader};
+use intcomputer::shell::Shell;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process;
+
+fn stdin_image() -> Result<image::Image, loader::Error> {
+    let mut bytes = Vec::new();
+    io::stdin().read_to_end(&mut bytes)?;
+    Loader::new().load_image(&bytes)
+}
+
+// Interactive shell around one vm, the optional script runs before the prompt.
+// With the program on stdin the prompt reads from /dev/tty, without a terminal only the script runs.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("usage: intcode <program> [script]   (- reads the program from stdin, commands from /dev/tty)");
+        process::exit(2);
+    }
+    let loaded = if args[0] == "-" { stdin_image() } else { image::read(&args[0]) };
+    let image = match loaded {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("{}: {}", args[0], e);
+            process::exit(1);
+        }
+    };
+    let mut shell = Shell::new(Amplifier::from_image(image, vec![]));
+
+    if let Some(file) = args.get(1) {
+        let script = fs::read_to_string(file).unwrap_or_else(|e| {
+            eprintln!("{}: {}", file, e);
+            process::exit(1);
+        });
+        match shell.script(&script) {
+            Ok(transcript) if !transcript.is_empty() => println!("{}", transcript),
+            Ok(_) => (),
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let stdin = io::stdin();
+    let commands: Box<dyn BufRead> = if args[0] == "-" {
+        match File::open("/dev/tty") {
+            Ok(tty) => Box::new(BufReader::new(tty)),
+            Err(_) if args.len() == 2 => return,
+            Err(e) => {
+                eprintln!("/dev/tty: {}, pass a script when the program comes from stdin", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        Box::new(stdin.lock())
+    };
+    let mut lines = commands.lines();
+    while !shell.finished {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        match shell.execute(&line) {
+            Ok(reply) if !reply.is_empty() => println!("{}", reply),
+            Ok(_) => (),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use intcomputer::conformance::{self, Case};
+use std::env;
+use std::path::Path;
+use std::process;
+
+// Runs conformance cases, arguments are case files or directories of them.
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: intconform <case or directory>...");
+        process::exit(2);
+    }
+    let mut cases: Vec<Case> = Vec::new();
+    for path in paths.iter() {
+        let loaded = if Path::new(path).is_dir() {
+            conformance::load_dir(path)
+        } else {
+            conformance::load(path).map(|case| vec![case])
+        };
+        match loaded {
+            Ok(loaded) => cases.extend(loaded),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    }
+    let mut failed = 0;
+    for case in cases.iter() {
+        let outcome = case.run();
+        if outcome.passed() {
+            println!("ok     {}", outcome.name);
+        } else {
+            failed += 1;
+            println!("FAILED {}", outcome.name);
+            for diff in outcome.diffs.iter() {
+                println!("       {}", diff);
+            }
+        }
+    }
+    println!("{} passed, {} failed", cases.len() - failed, failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,84 @@
+use intcomputer::image::{self, Image};
+use intcomputer::loader::Loader;
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+// Converts text programs into binary images and back, whichever the input is.
+fn usage() -> ! {
+    eprintln!("usage: intconv [--entry N] [--memory N] <input> <output>   (- for stdin/stdout)");
+    process::exit(2);
+}
+
+fn main() {
+    let mut entry = None;
+    let mut memory = None;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--entry" => entry = Some(args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage())),
+            "--memory" => memory = Some(args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage())),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 {
+        usage();
+    }
+
+    let mut input = Vec::new();
+    let read = if files[0] == "-" {
+        io::stdin().read_to_end(&mut input).map(|_| ())
+    } else {
+        fs::read(&files[0]).map(|bytes| input = bytes)
+    };
+    if let Err(e) = read {
+        eprintln!("{}: {}", files[0], e);
+        process::exit(1);
+    }
+
+    let output = if image::is_image(&input) {
+        match image::decode(&input) {
+            // text has no header, refuse instead of losing the entry point or memory hint
+            Ok(image) if image.entry != 0 || image.memory_hint > image.program.len() => {
+                eprintln!("{}: entry point {} and memory hint {} cannot be stored as text",
+                          files[0], image.entry, image.memory_hint);
+                process::exit(1);
+            }
+            Ok(image) => image::to_text(&image.program).into_bytes(),
+            Err(e) => {
+                eprintln!("{}: {}", files[0], e);
+                process::exit(1);
+            }
+        }
+    } else {
+        let loader = Loader { allow_comments: true, ..Loader::new() };
+        match loader.load_bytes(&input) {
+            Ok(program) => {
+                let mut image = Image::new(program);
+                image.entry = entry.unwrap_or(image.entry);
+                image.memory_hint = memory.unwrap_or(image.memory_hint);
+                if let Err(e) = image::validate(&image) {
+                    eprintln!("{}: {}", files[0], e);
+                    process::exit(1);
+                }
+                image::encode(&image)
+            }
+            Err(e) => {
+                eprintln!("{}: {}", files[0], e);
+                process::exit(1);
+            }
+        }
+    };
+
+    let written = if files[1] == "-" {
+        io::stdout().write_all(&output)
+    } else {
+        fs::write(&files[1], &output)
+    };
+    if let Err(e) = written {
+        eprintln!("{}: {}", files[1], e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,16 @@
+use intcomputer::decompile;
+use intcomputer::intcode;
+use std::env;
+use std::process;
+
+fn main() {
+    let file = match env::args().nth(1) {
+        Some(file) => file,
+        None => {
+            eprintln!("usage: intdecompile <program>");
+            process::exit(2);
+        }
+    };
+    let program = intcode::read_data(&file);
+    print!("{}", decompile::decompile(&program));
+}
@@ -0,0 +1,43 @@
+use intcomputer::gdb::Stub;
+use intcomputer::intcode::Amplifier;
+use intcomputer::image;
+use std::env;
+use std::net::TcpListener;
+use std::process;
+
+// Waits for a debugger on localhost, e.g. `target remote :1234` in gdb.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("usage: intgdb <program> [port]");
+        process::exit(2);
+    }
+    let image = match image::read(&args[0]) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("{}: {}", args[0], e);
+            process::exit(1);
+        }
+    };
+    let port: u16 = match args.get(1).map(|p| p.parse()) {
+        None => 1234,
+        Some(Ok(port)) => port,
+        Some(Err(_)) => {
+            eprintln!("invalid port {}", args[1]);
+            process::exit(2);
+        }
+    };
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("127.0.0.1:{}: {}", port, e);
+            process::exit(1);
+        }
+    };
+    eprintln!("listening on 127.0.0.1:{}", port);
+    let mut stub = Stub::new(Amplifier::from_image(image, vec![]));
+    if let Err(e) = stub.serve(&listener) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,36 @@
+use intcomputer::analysis::{self, Severity};
+use intcomputer::loader::{self, Loader};
+use std::env;
+use std::process;
+
+fn main() {
+    let files: Vec<String> = env::args().skip(1).collect();
+    if files.is_empty() {
+        eprintln!("usage: intlint <program>...   (- reads stdin)");
+        process::exit(2);
+    }
+    let mut errors = 0;
+    for file in files.iter() {
+        let loaded = if file == "-" { Loader::new().load_stdin() } else { loader::load(file) };
+        let program = match loaded {
+            Ok(program) => program,
+            Err(e) => {
+                println!("{}: {}", file, e);
+                errors += 1;
+                continue;
+            }
+        };
+        let analysis = analysis::analyze(&program);
+        for diagnostic in analysis.diagnostics.iter() {
+            println!("{}:{}", file, diagnostic);
+            if diagnostic.severity() == Severity::Error {
+                errors += 1;
+            }
+        }
+        println!("{}: {} reachable instructions, {} diagnostics",
+                 file, analysis.reachable.len(), analysis.diagnostics.len());
+    }
+    if errors > 0 {
+        process::exit(1);
+    }
+}
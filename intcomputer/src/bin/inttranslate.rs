@@ -0,0 +1,15 @@
+use intcomputer::intcode;
+use intcomputer::translate;
+use std::env;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("usage: inttranslate <program> [module]");
+        process::exit(2);
+    }
+    let program = intcode::read_data(&args[0]);
+    let name = args.get(1).map(|s| s.as_str()).unwrap_or("program");
+    print!("{}", translate::translate(&program, name));
+}
@@ -0,0 +1,301 @@
+use crate::intcode::{Amplifier, Event, Profile};
+use crate::loader;
+use crate::patch::{self, Patch};
+use std::error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// Conformance cases, one `.case` file each:
+//
+//   # equal in position mode
+//   program: 3,9,8,9,10,9,4,9,99,-1,8
+//   input: 8
+//   output: 1
+//
+// Keys:
+//   program        the program inline, or
+//   program-file   a program file, relative to the case file
+//   profile        day2, day5 or day9 (default)
+//   patch          `address: original -> new`, applied before the run, may repeat
+//   input          queued before the run
+//   output         the complete output, `...` at the end only checks a prefix
+//   memory         expected start of the memory after the run
+//   fault          expected fault message, the run has to halt otherwise
+//
+// Lists are separated by commas, a line without a key continues the previous one.
+pub const EXTENSION: &str = "case";
+
+// runs that take longer count as hanging
+const MAX_STEPS: usize = 50_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub name: String,
+    pub program: Vec<isize>,
+    pub profile: Profile,
+    pub patches: Vec<Patch>,
+    pub inputs: Vec<isize>,
+    pub outputs: Vec<isize>,
+    // only the first outputs are checked
+    pub partial_output: bool,
+    pub memory: Option<Vec<isize>>,
+    pub fault: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(String),
+    // line is 1-based
+    Parse { case: String, line: usize, message: String },
+    Missing { case: String, key: &'static str },
+    Program { case: String, error: loader::Error },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse { case, line, message } => write!(f, "{}:{}: {}", case, line, message),
+            Error::Missing { case, key } => write!(f, "{}: missing {}", case, key),
+            Error::Program { case, error } => write!(f, "{}: program: {}", case, error),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub name: String,
+    pub diffs: Vec<String>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.diffs.is_empty()
+    }
+}
+
+fn parse_list(text: &str) -> Result<Vec<isize>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse().map_err(|_| format!("invalid value {:?}", value)))
+        .collect()
+}
+
+fn parse_profile(text: &str) -> Result<Profile, String> {
+    match text {
+        "day2" => Ok(Profile::Day2),
+        "day5" => Ok(Profile::Day5),
+        "day9" => Ok(Profile::Day9),
+        _ => Err(format!("unknown profile {:?}", text)),
+    }
+}
+
+// `dir` is where program files are looked up
+pub fn parse(name: &str, source: &str, dir: &Path) -> Result<Case, Error> {
+    // joins continuation lines to their key first
+    let mut entries: Vec<(usize, String, String)> = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let text = match line.find('#') {
+            Some(i) => &line[..i],
+            None => line,
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+        let error =
+            |message: &str| Error::Parse { case: name.to_string(), line: number + 1, message: message.to_string() };
+        match text.find(':') {
+            Some(colon) => {
+                entries.push((number + 1, text[..colon].trim().to_string(), text[colon + 1..].trim().to_string()))
+            }
+            None => {
+                let (_, _, value) = entries.last_mut().ok_or_else(|| error("continuation without a key"))?;
+                value.push(' ');
+                value.push_str(text.trim());
+            }
+        }
+    }
+
+    let mut case = Case {
+        name: name.to_string(),
+        program: Vec::new(),
+        profile: Profile::Day9,
+        patches: Vec::new(),
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        partial_output: false,
+        memory: None,
+        fault: None,
+    };
+    let mut has_output = false;
+    for (line, key, value) in entries {
+        let error = |message: String| Error::Parse { case: name.to_string(), line, message };
+        match key.as_str() {
+            "program" => case.program = parse_list(&value).map_err(error)?,
+            "program-file" => {
+                case.program = loader::load(dir.join(&value))
+                    .map_err(|error| Error::Program { case: name.to_string(), error })?
+            }
+            "profile" => case.profile = parse_profile(&value).map_err(error)?,
+            "patch" => {
+                let change = patch::parse_change(&value).ok_or_else(|| error(format!("invalid patch {:?}", value)))?;
+                case.patches.push(Patch { name: format!("line {}", line), changes: vec![change] });
+            }
+            "input" => case.inputs.extend(parse_list(&value).map_err(error)?),
+            "output" => {
+                let value = value.trim_end();
+                case.partial_output = value.ends_with("...");
+                case.outputs = parse_list(value.trim_end_matches("...")).map_err(error)?;
+                has_output = true;
+            }
+            "memory" => case.memory = Some(parse_list(&value).map_err(error)?),
+            "fault" => case.fault = Some(value),
+            _ => return Err(error(format!("unknown key {:?}", key))),
+        }
+    }
+    if case.program.is_empty() {
+        return Err(Error::Missing { case: name.to_string(), key: "program" });
+    }
+    if !has_output && case.memory.is_none() && case.fault.is_none() {
+        return Err(Error::Missing { case: name.to_string(), key: "output, memory or fault" });
+    }
+    Ok(case)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Case, Error> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))?;
+    let name = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+    parse(&name, &source, path.parent().unwrap_or_else(|| Path::new(".")))
+}
+
+// all cases of a directory, sorted by file name
+pub fn load_dir<P: AsRef<Path>>(dir: P) -> Result<Vec<Case>, Error> {
+    let dir = dir.as_ref();
+    let entries = fs::read_dir(dir).map_err(|e| Error::Io(format!("{}: {}", dir.display(), e)))?;
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| Error::Io(format!("{}: {}", dir.display(), e)))?.path();
+        if path.extension().is_some_and(|extension| extension == EXTENSION) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths.iter().map(load).collect()
+}
+
+fn list(values: &[isize]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn compare(what: &str, expected: &[isize], actual: &[isize], diffs: &mut Vec<String>) {
+    if let Some(i) = expected.iter().zip(actual.iter()).position(|(e, a)| e != a) {
+        diffs.push(format!("{} {}: expected {}, got {}", what, i, expected[i], actual[i]));
+    } else if actual.len() < expected.len() {
+        let missing = &expected[actual.len()..];
+        diffs.push(format!("{}: expected {} more: {}", what, missing.len(), list(missing)));
+    }
+}
+
+impl Case {
+    pub fn run(&self) -> Outcome {
+        let mut diffs = Vec::new();
+        let mut amp = Amplifier::new(self.program.clone(), self.inputs.clone());
+        amp.set_profile(self.profile);
+        for patch in self.patches.iter() {
+            if let Err(e) = amp.apply_patch(patch) {
+                diffs.push(e.to_string());
+                return Outcome { name: self.name.clone(), diffs };
+            }
+        }
+
+        let mut outputs = Vec::new();
+        let mut fault = None;
+        let mut steps = 0;
+        loop {
+            if self.partial_output && outputs.len() >= self.outputs.len() && self.memory.is_none() {
+                break;
+            }
+            if steps == MAX_STEPS {
+                diffs.push(format!("still running after {} steps", MAX_STEPS));
+                break;
+            }
+            steps += 1;
+            match amp.step(false) {
+                Ok(None) | Ok(Some(Event::Watchpoint(_))) => (),
+                Ok(Some(Event::Output(value))) => outputs.push(value),
+                Ok(Some(Event::NeedInput)) => {
+                    diffs.push(format!("waiting for input at {}", amp.ip()));
+                    break;
+                }
+                Ok(Some(Event::Halted)) => break,
+                Err(f) => {
+                    fault = Some(f.to_string());
+                    break;
+                }
+            }
+        }
+
+        match (&self.fault, fault) {
+            (Some(expected), Some(actual)) if *expected != actual =>
+                diffs.push(format!("fault: expected {:?}, got {:?}", expected, actual)),
+            (Some(expected), None) => diffs.push(format!("fault: expected {:?}, got none", expected)),
+            (None, Some(actual)) => diffs.push(format!("fault: {}", actual)),
+            _ => (),
+        }
+        compare("output", &self.outputs, &outputs, &mut diffs);
+        if !self.partial_output && outputs.len() > self.outputs.len() {
+            diffs.push(format!("output: {} more than expected: {}", outputs.len() - self.outputs.len(),
+                               list(&outputs[self.outputs.len()..])));
+        }
+        if let Some(memory) = &self.memory {
+            compare("memory", memory, amp.memory(), &mut diffs);
+        }
+        Outcome { name: self.name.clone(), diffs }
+    }
+}
+
+pub fn run_dir<P: AsRef<Path>>(dir: P) -> Result<Vec<Outcome>, Error> {
+    Ok(load_dir(dir)?.iter().map(Case::run).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conformance::{self, Error};
+    use std::path::Path;
+
+    #[test]
+    fn suite() {
+        let outcomes = conformance::run_dir("conformance").unwrap();
+        assert!(outcomes.len() > 20);
+        let failed: Vec<String> =
+            outcomes.iter().filter(|o| !o.passed()).map(|o| format!("{}: {}", o.name, o.diffs.join("; "))).collect();
+        assert!(failed.is_empty(), "{}", failed.join("\n"));
+    }
+
+    #[test]
+    fn diffs() {
+        let source = "
+            program: 104,1,104,2,
+                     104,3,99
+            output: 1,5 ...
+            memory: 104,2
+        ";
+        let case = conformance::parse("diffs", source, Path::new(".")).unwrap();
+        assert!(case.partial_output);
+        assert_eq!(vec!["output 1: expected 5, got 2", "memory 1: expected 2, got 1"], case.run().diffs);
+
+        let source = "program: 3,0,99\noutput:\nfault: Illegal Opcode 12 at 0";
+        let case = conformance::parse("input", source, Path::new(".")).unwrap();
+        assert_eq!(vec!["waiting for input at 0", "fault: expected \"Illegal Opcode 12 at 0\", got none"],
+                   case.run().diffs);
+
+        assert!(matches!(conformance::parse("x", "program: 99", Path::new(".")), Err(Error::Missing { .. })));
+        assert!(matches!(conformance::parse("x", "program: 99\nouput: 1", Path::new(".")),
+                         Err(Error::Parse { line: 2, .. })));
+    }
+}
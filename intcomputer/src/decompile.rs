@@ -0,0 +1,666 @@
+use crate::analysis::{self, Decoded};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+// Recovers functions and control flow from programs built by the usual
+// intcode compiler: a function starts with `arb N`, ends with `arb -N`
+// followed by a jump to [rb+0], and a call stores the return address in
+// [rb+0] and the arguments in [rb+1].. right before jumping to the callee.
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Operand {
+    mode: isize,
+    value: isize,
+    at: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Value(Operand),
+    Add(Operand, Operand),
+    Mul(Operand, Operand),
+    LessThan(Operand, Operand),
+    Equals(Operand, Operand),
+    Input,
+}
+
+#[derive(Debug, Clone)]
+enum Stmt {
+    Assign(Operand, Expr),
+    Output(Operand),
+    AdjustRb(Operand),
+    Call(usize, BTreeMap<isize, Expr>),
+    Jump(Option<(Operand, bool)>, Operand),
+    Halt,
+}
+
+#[derive(Debug, Clone)]
+struct Item {
+    addr: usize,
+    end: usize,
+    delta: isize,
+    stmt: Stmt,
+}
+
+#[derive(Debug, Clone)]
+struct Cond {
+    expr: Expr,
+    truth: bool,
+}
+
+impl Cond {
+    fn negate(self) -> Cond {
+        Cond { expr: self.expr, truth: !self.truth }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Stmt(usize, isize, Stmt),
+    If(usize, isize, Cond, Vec<Node>, Vec<Node>),
+    While(usize, isize, Option<Cond>, Vec<Node>),
+    DoWhile(usize, isize, Cond, Vec<Node>),
+    Break,
+    Continue,
+    Goto(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub entry: usize,
+    pub frame: isize,
+    pub params: usize,
+    pub calls: BTreeSet<usize>,
+    instructions: BTreeMap<usize, (Decoded, isize)>,
+}
+
+fn operands(inst: &Decoded) -> Vec<Operand> {
+    inst.params
+        .iter()
+        .enumerate()
+        .map(|(i, value)| Operand { mode: inst.modes[i], value: *value, at: inst.address + 1 + i })
+        .collect()
+}
+
+fn is_unconditional(inst: &Decoded) -> bool {
+    (inst.opcode == 5 || inst.opcode == 6)
+        && inst.modes[0] == 1
+        && (inst.params[0] != 0) == (inst.opcode == 5)
+}
+
+fn stored_immediate(inst: &Decoded) -> Option<isize> {
+    let ops = operands(inst);
+    match inst.opcode {
+        1 if ops[0].mode == 1 && ops[1] == (Operand { mode: 1, value: 0, at: ops[1].at }) => Some(ops[0].value),
+        1 if ops[1].mode == 1 && ops[0] == (Operand { mode: 1, value: 0, at: ops[0].at }) => Some(ops[1].value),
+        2 if ops[0].mode == 1 && ops[1] == (Operand { mode: 1, value: 1, at: ops[1].at }) => Some(ops[0].value),
+        2 if ops[1].mode == 1 && ops[0] == (Operand { mode: 1, value: 1, at: ops[0].at }) => Some(ops[1].value),
+        _ => None,
+    }
+}
+
+fn writes_relative(inst: &Decoded) -> Option<isize> {
+    match inst.opcode {
+        1 | 2 | 7 | 8 if inst.modes[2] == 2 => Some(inst.params[2]),
+        3 if inst.modes[0] == 2 => Some(inst.params[0]),
+        _ => None,
+    }
+}
+
+fn expression(inst: &Decoded) -> Expr {
+    let ops = operands(inst);
+    match inst.opcode {
+        1 if ops[1].mode == 1 && ops[1].value == 0 => Expr::Value(ops[0]),
+        1 if ops[0].mode == 1 && ops[0].value == 0 => Expr::Value(ops[1]),
+        2 if ops[1].mode == 1 && ops[1].value == 1 => Expr::Value(ops[0]),
+        2 if ops[0].mode == 1 && ops[0].value == 1 => Expr::Value(ops[1]),
+        1 => Expr::Add(ops[0], ops[1]),
+        2 => Expr::Mul(ops[0], ops[1]),
+        7 => Expr::LessThan(ops[0], ops[1]),
+        8 => Expr::Equals(ops[0], ops[1]),
+        3 => Expr::Input,
+        _ => panic!("instruction {} has no expression", inst.opcode),
+    }
+}
+
+fn jump_target(inst: &Decoded) -> Option<usize> {
+    if inst.modes[1] == 1 && inst.params[1] >= 0 {
+        Some(inst.params[1] as usize)
+    } else {
+        None
+    }
+}
+
+// A call is an unconditional jump to a fixed address right after storing its
+// own return address in [rb+0].
+fn call_target(program: &[isize], previous: Option<&Decoded>, inst: &Decoded) -> Option<usize> {
+    let previous = previous?;
+    if !is_unconditional(inst) || previous.address + previous.width() != inst.address {
+        return None;
+    }
+    let target = jump_target(inst)?;
+    if writes_relative(previous) == Some(0)
+        && stored_immediate(previous) == Some((inst.address + inst.width()) as isize)
+        && target < program.len()
+    {
+        Some(target)
+    } else {
+        None
+    }
+}
+
+fn discover(program: &[isize], entry: usize) -> Function {
+    let mut instructions = BTreeMap::new();
+    let mut calls = BTreeSet::new();
+    let mut worklist = vec![(entry, 0, None)];
+    let frame = match analysis::decode(program, entry) {
+        Ok(ref inst) if entry != 0 && inst.opcode == 9 && inst.modes[0] == 1 => inst.params[0],
+        _ => 0,
+    };
+
+    while let Some((ip, delta, previous)) = worklist.pop() {
+        if instructions.contains_key(&ip) {
+            continue;
+        }
+        let inst = match analysis::decode(program, ip) {
+            Ok(inst) => inst,
+            Err(_) => continue,
+        };
+        let next = ip + inst.width();
+        let mut next_delta = delta;
+        if inst.opcode == 9 && inst.modes[0] == 1 && !(entry == 0 && ip == 0) {
+            next_delta += inst.params[0];
+        }
+        match inst.opcode {
+            99 => {}
+            5 | 6 => {
+                if let Some(callee) = call_target(program, instructions.get(&previous.unwrap_or(usize::MAX)).map(|(i, _)| i), &inst) {
+                    calls.insert(callee);
+                    worklist.push((next, delta, None));
+                } else {
+                    if !is_unconditional(&inst) {
+                        worklist.push((next, delta, Some(ip)));
+                    }
+                    if let Some(target) = jump_target(&inst) {
+                        worklist.push((target, delta, None));
+                    }
+                }
+            }
+            _ => worklist.push((next, next_delta, Some(ip))),
+        }
+        instructions.insert(ip, (inst, delta));
+    }
+
+    Function { entry, frame, params: 0, calls, instructions }
+}
+
+pub fn functions(program: &[isize]) -> Vec<Function> {
+    let mut found: BTreeMap<usize, Function> = BTreeMap::new();
+    let mut worklist = vec![0];
+    while let Some(entry) = worklist.pop() {
+        if found.contains_key(&entry) {
+            continue;
+        }
+        let function = discover(program, entry);
+        worklist.extend(function.calls.iter());
+        found.insert(entry, function);
+    }
+    let mut params: BTreeMap<usize, usize> = BTreeMap::new();
+    for function in found.values() {
+        for item in items(program, function) {
+            if let Stmt::Call(callee, args) = item.stmt {
+                let count = args.keys().max().cloned().unwrap_or(0) as usize;
+                let entry = params.entry(callee).or_insert(0);
+                *entry = (*entry).max(count);
+            }
+        }
+    }
+    for function in found.values_mut() {
+        let locals = (function.frame - 1).max(0) as usize;
+        function.params = params.get(&function.entry).cloned().unwrap_or(0).min(locals);
+    }
+    found.into_values().collect()
+}
+
+fn items(program: &[isize], function: &Function) -> Vec<Item> {
+    let mut items: Vec<Item> = Vec::new();
+    let mut previous: Option<&Decoded> = None;
+    for (inst, delta) in function.instructions.values() {
+        let ops = operands(inst);
+        let stmt = match inst.opcode {
+            1 | 2 | 7 | 8 => Stmt::Assign(ops[2], expression(inst)),
+            3 => Stmt::Assign(ops[0], Expr::Input),
+            4 => Stmt::Output(ops[0]),
+            9 => Stmt::AdjustRb(ops[0]),
+            99 => Stmt::Halt,
+            5 | 6 => match call_target(program, previous, inst) {
+                Some(callee) => {
+                    items.pop();
+                    let mut args = BTreeMap::new();
+                    let mut addr = previous.unwrap().address;
+                    while let Some(Item { stmt: Stmt::Assign(dest, expr), .. }) = items.last() {
+                        if dest.mode != 2 || dest.value < 1 || args.contains_key(&dest.value) {
+                            break;
+                        }
+                        args.insert(dest.value, expr.clone());
+                        addr = items.last().unwrap().addr;
+                        items.pop();
+                    }
+                    items.push(Item { addr, end: inst.address + inst.width(), delta: *delta, stmt: Stmt::Call(callee, args) });
+                    previous = Some(inst);
+                    continue;
+                }
+                None if is_unconditional(inst) => Stmt::Jump(None, ops[1]),
+                None => Stmt::Jump(Some((ops[0], inst.opcode == 5)), ops[1]),
+            },
+            _ => unreachable!(),
+        };
+        items.push(Item { addr: inst.address, end: inst.address + inst.width(), delta: *delta, stmt });
+        previous = Some(inst);
+    }
+    items
+}
+
+struct Structurer<'a> {
+    items: &'a [Item],
+    index: BTreeMap<usize, usize>,
+}
+
+impl<'a> Structurer<'a> {
+    fn index_of(&self, addr: usize, hi: usize) -> Option<usize> {
+        if hi > 0 && self.items[hi - 1].end == addr {
+            return Some(hi);
+        }
+        self.index.get(&addr).cloned().filter(|i| *i <= hi)
+    }
+
+    fn static_jump(&self, i: usize) -> Option<(Option<(Operand, bool)>, usize)> {
+        match &self.items[i].stmt {
+            Stmt::Jump(cond, target) if target.mode == 1 && target.value >= 0 => Some((*cond, target.value as usize)),
+            _ => None,
+        }
+    }
+
+    fn condition(&self, nodes: &mut Vec<Node>, operand: Operand, taken_if_true: bool) -> Cond {
+        let folded = match nodes.last() {
+            Some(Node::Stmt(_, _, Stmt::Assign(dest, expr))) if dest.mode == operand.mode && dest.value == operand.value && dest.mode == 2 => Some(expr.clone()),
+            _ => None,
+        };
+        let expr = match folded {
+            Some(expr) => {
+                nodes.pop();
+                expr
+            }
+            None => Expr::Value(operand),
+        };
+        Cond { expr, truth: taken_if_true }
+    }
+
+    fn emit(&self, lo: usize, hi: usize, ctx: Option<(usize, usize)>, skip_loop: Option<usize>) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut i = lo;
+        while i < hi {
+            let item = &self.items[i];
+            if skip_loop != Some(i) {
+                let back = (i..hi).rev().find(|j| match self.static_jump(*j) {
+                    Some((_, target)) => target == item.addr,
+                    None => false,
+                });
+                if let Some(j) = back {
+                    let exit = self.items[j].end;
+                    let body = self.emit(i, j, Some((item.addr, exit)), Some(i));
+                    let (cond, _) = self.static_jump(j).unwrap();
+                    nodes.push(match cond {
+                        None => self.while_loop(item.addr, item.delta, body),
+                        Some((operand, truth)) => {
+                            let mut body = body;
+                            let cond = self.condition(&mut body, operand, truth);
+                            Node::DoWhile(item.addr, item.delta, cond, body)
+                        }
+                    });
+                    i = j + 1;
+                    continue;
+                }
+            }
+            match self.static_jump(i) {
+                Some((cond, target)) => {
+                    let node = if ctx.map(|c| c.1) == Some(target) {
+                        Some(Node::Break)
+                    } else if ctx.map(|c| c.0) == Some(target) {
+                        Some(Node::Continue)
+                    } else {
+                        None
+                    };
+                    match (cond, node) {
+                        (None, Some(node)) => nodes.push(node),
+                        (Some((operand, truth)), Some(node)) => {
+                            let cond = self.condition(&mut nodes, operand, truth);
+                            nodes.push(Node::If(item.addr, item.delta, cond, vec![node], vec![]));
+                        }
+                        (Some((operand, truth)), None) if target > item.addr => match self.index_of(target, hi) {
+                            Some(t) if t > i => {
+                                let cond = self.condition(&mut nodes, operand, truth).negate();
+                                let otherwise = match self.static_jump(t - 1) {
+                                    Some((None, m)) if t - 1 > i && m > target && ctx.map(|c| c.1) != Some(m) => self.index_of(m, hi).filter(|m| *m > t),
+                                    _ => None,
+                                };
+                                match otherwise {
+                                    Some(m) => {
+                                        let then = self.emit(i + 1, t - 1, ctx, None);
+                                        let other = self.emit(t, m, ctx, None);
+                                        nodes.push(Node::If(item.addr, item.delta, cond, then, other));
+                                        i = m;
+                                    }
+                                    None => {
+                                        let then = self.emit(i + 1, t, ctx, None);
+                                        nodes.push(Node::If(item.addr, item.delta, cond, then, vec![]));
+                                        i = t;
+                                    }
+                                }
+                                continue;
+                            }
+                            _ => {
+                                let cond = self.condition(&mut nodes, operand, truth);
+                                nodes.push(Node::If(item.addr, item.delta, cond, vec![Node::Goto(target)], vec![]));
+                            }
+                        },
+                        (Some((operand, truth)), None) => {
+                            let cond = self.condition(&mut nodes, operand, truth);
+                            nodes.push(Node::If(item.addr, item.delta, cond, vec![Node::Goto(target)], vec![]));
+                        }
+                        (None, None) => nodes.push(Node::Goto(target)),
+                    }
+                }
+                None => nodes.push(Node::Stmt(item.addr, item.delta, item.stmt.clone())),
+            }
+            i += 1;
+        }
+        nodes
+    }
+
+    fn while_loop(&self, addr: usize, delta: isize, mut body: Vec<Node>) -> Node {
+        let exits_first = match body.first() {
+            Some(Node::If(_, _, _, then, other)) => other.is_empty() && then.len() == 1 && matches!(then[0], Node::Break),
+            _ => false,
+        };
+        if exits_first {
+            if let Node::If(_, _, cond, _, _) = body.remove(0) {
+                return Node::While(addr, delta, Some(cond.negate()), body);
+            }
+        }
+        Node::While(addr, delta, None, body)
+    }
+}
+
+struct Printer<'a> {
+    function: &'a Function,
+    patched: &'a BTreeSet<usize>,
+    labels: BTreeSet<usize>,
+    out: String,
+}
+
+impl<'a> Printer<'a> {
+    fn slot(&self, value: isize, delta: isize) -> String {
+        if self.function.entry == 0 {
+            return match value + delta {
+                0 => String::from("call_ret"),
+                k if k > 0 => format!("out{}", k),
+                k => format!("frame[{}]", k),
+            };
+        }
+        let frame = self.function.frame;
+        let params = self.function.params as isize;
+        match value + delta {
+            0 => String::from("ret_addr"),
+            k if k > 0 && k <= params => format!("arg{}", k),
+            k if k > params && k < frame => format!("local{}", k - params),
+            k if k == frame => String::from("call_ret"),
+            k if k > frame => format!("out{}", k - frame),
+            k => format!("frame[{}]", k),
+        }
+    }
+
+    fn operand(&self, op: &Operand, delta: isize) -> String {
+        if self.patched.contains(&op.at) {
+            return match op.mode {
+                0 => format!("mem[code[{}]]", op.at),
+                1 => format!("code[{}]", op.at),
+                _ => format!("mem[rb + code[{}]]", op.at),
+            };
+        }
+        match op.mode {
+            0 if self.patched.contains(&(op.value as usize)) => format!("code[{}]", op.value),
+            0 => format!("mem[{}]", op.value),
+            1 => format!("{}", op.value),
+            _ => self.slot(op.value, delta),
+        }
+    }
+
+    fn expr(&self, expr: &Expr, delta: isize) -> String {
+        match expr {
+            Expr::Value(a) => self.operand(a, delta),
+            Expr::Add(a, b) if b.mode == 1 && b.value < 0 => format!("{} - {}", self.operand(a, delta), -b.value),
+            Expr::Add(a, b) => format!("{} + {}", self.operand(a, delta), self.operand(b, delta)),
+            Expr::Mul(a, b) if b.mode == 1 && b.value == -1 => format!("-{}", self.operand(a, delta)),
+            Expr::Mul(a, b) if a.mode == 1 && a.value == -1 => format!("-{}", self.operand(b, delta)),
+            Expr::Mul(a, b) => format!("{} * {}", self.operand(a, delta), self.operand(b, delta)),
+            Expr::LessThan(a, b) => format!("{} < {}", self.operand(a, delta), self.operand(b, delta)),
+            Expr::Equals(a, b) => format!("{} == {}", self.operand(a, delta), self.operand(b, delta)),
+            Expr::Input => String::from("input()"),
+        }
+    }
+
+    fn cond(&self, cond: &Cond, delta: isize) -> String {
+        match (&cond.expr, cond.truth) {
+            (_, true) => self.expr(&cond.expr, delta),
+            (Expr::LessThan(a, b), false) => format!("{} >= {}", self.operand(a, delta), self.operand(b, delta)),
+            (Expr::Equals(a, b), false) => format!("{} != {}", self.operand(a, delta), self.operand(b, delta)),
+            (Expr::Value(a), false) => format!("!{}", self.operand(a, delta)),
+            (expr, false) => format!("!({})", self.expr(expr, delta)),
+        }
+    }
+
+    fn line(&mut self, depth: usize, text: &str) {
+        for _ in 0..depth {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn label(&mut self, addr: usize) {
+        if self.labels.contains(&addr) {
+            self.out.push_str(&format!("L{}:\n", addr));
+        }
+    }
+
+    fn stmt(&self, stmt: &Stmt, delta: isize) -> String {
+        match stmt {
+            Stmt::Assign(dest, expr) => format!("{} = {};", self.operand(dest, delta), self.expr(expr, delta)),
+            Stmt::Output(a) => format!("output({});", self.operand(a, delta)),
+            Stmt::AdjustRb(a) if a.mode == 1 && self.function.entry == 0 && delta == 0 => format!("rb = {};", a.value),
+            Stmt::AdjustRb(a) if a.mode == 1 && (a.value == self.function.frame || a.value == -self.function.frame) => String::new(),
+            Stmt::AdjustRb(a) => format!("rb += {};", self.operand(a, delta)),
+            Stmt::Call(callee, args) => {
+                let count = args.keys().max().cloned().unwrap_or(0);
+                let rendered: Vec<String> = (1..=count)
+                    .map(|k| match args.get(&k) {
+                        Some(expr) => self.expr(expr, delta),
+                        None => String::from("_"),
+                    })
+                    .collect();
+                format!("f{}({});", callee, rendered.join(", "))
+            }
+            Stmt::Jump(None, target) if target.mode == 2 && target.value + delta == 0 => String::from("return;"),
+            Stmt::Jump(None, target) => format!("goto *{};", self.operand(target, delta)),
+            Stmt::Jump(Some((a, truth)), target) => {
+                format!("if ({}{}) goto *{};", if *truth { "" } else { "!" }, self.operand(a, delta), self.operand(target, delta))
+            }
+            Stmt::Halt => String::from("halt();"),
+        }
+    }
+
+    fn nodes(&mut self, nodes: &[Node], depth: usize) {
+        for node in nodes {
+            match node {
+                Node::Stmt(addr, delta, stmt) => {
+                    self.label(*addr);
+                    let text = self.stmt(stmt, *delta);
+                    if !text.is_empty() {
+                        self.line(depth, &text);
+                    }
+                }
+                Node::If(addr, delta, cond, then, other) => {
+                    self.label(*addr);
+                    let text = format!("if ({}) {{", self.cond(cond, *delta));
+                    self.line(depth, &text);
+                    self.nodes(then, depth + 1);
+                    if !other.is_empty() {
+                        self.line(depth, "} else {");
+                        self.nodes(other, depth + 1);
+                    }
+                    self.line(depth, "}");
+                }
+                Node::While(addr, delta, cond, body) => {
+                    self.label(*addr);
+                    let text = match cond {
+                        Some(cond) => format!("while ({}) {{", self.cond(cond, *delta)),
+                        None => String::from("loop {"),
+                    };
+                    self.line(depth, &text);
+                    self.nodes(body, depth + 1);
+                    self.line(depth, "}");
+                }
+                Node::DoWhile(addr, delta, cond, body) => {
+                    self.label(*addr);
+                    self.line(depth, "do {");
+                    self.nodes(body, depth + 1);
+                    let text = format!("}} while ({});", self.cond(cond, *delta));
+                    self.line(depth, &text);
+                }
+                Node::Break => self.line(depth, "break;"),
+                Node::Continue => self.line(depth, "continue;"),
+                Node::Goto(target) => {
+                    let text = format!("goto L{};", target);
+                    self.line(depth, &text);
+                }
+            }
+        }
+    }
+}
+
+fn gotos(nodes: &[Node], into: &mut BTreeSet<usize>) {
+    for node in nodes {
+        match node {
+            Node::Goto(target) => {
+                into.insert(*target);
+            }
+            Node::If(_, _, _, then, other) => {
+                gotos(then, into);
+                gotos(other, into);
+            }
+            Node::While(_, _, _, body) | Node::DoWhile(_, _, _, body) => gotos(body, into),
+            _ => {}
+        }
+    }
+}
+
+pub struct Decompiled {
+    pub functions: Vec<Function>,
+    pub source: String,
+}
+
+impl fmt::Display for Decompiled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+pub fn decompile(program: &[isize]) -> Decompiled {
+    let functions = functions(program);
+    let mut slots = BTreeSet::new();
+    let mut targets = BTreeSet::new();
+    for function in functions.iter() {
+        for (inst, _) in function.instructions.values() {
+            slots.extend(inst.address..inst.address + inst.width());
+            let written = match inst.opcode {
+                1 | 2 | 7 | 8 => Some(2),
+                3 => Some(0),
+                _ => None,
+            };
+            if let Some(i) = written {
+                if inst.modes[i] == 0 && inst.params[i] >= 0 {
+                    targets.insert(inst.params[i] as usize);
+                }
+            }
+        }
+    }
+    let patched: BTreeSet<usize> = slots.intersection(&targets).cloned().collect();
+    let mut source = String::new();
+    for function in functions.iter() {
+        let items = items(program, function);
+        let index = items.iter().enumerate().map(|(i, item)| (item.addr, i)).collect();
+        let structurer = Structurer { items: &items, index };
+        let nodes = structurer.emit(0, items.len(), None, None);
+        let mut labels = BTreeSet::new();
+        gotos(&nodes, &mut labels);
+        let mut printer = Printer { function, patched: &patched, labels, out: String::new() };
+        printer.nodes(&nodes, 1);
+
+        let params: Vec<String> = (1..=function.params).map(|k| format!("arg{}", k)).collect();
+        if function.entry == 0 {
+            source.push_str("fn main() {\n");
+        } else {
+            source.push_str(&format!("fn f{}({}) {{\n", function.entry, params.join(", ")));
+        }
+        source.push_str(&printer.out);
+        source.push_str("}\n\n");
+    }
+    Decompiled { functions, source }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decompile::decompile;
+
+    #[test]
+    fn straight_line() {
+        let decompiled = decompile(&[3, 9, 1002, 9, 2, 10, 4, 10, 99, 0, 0]);
+        assert_eq!("fn main() {\n    mem[9] = input();\n    mem[10] = mem[9] * 2;\n    output(mem[10]);\n    halt();\n}\n\n", decompiled.source);
+    }
+
+    #[test]
+    fn if_from_jump_if_false() {
+        // if (input() == 8) output(1); halt
+        let program = vec![3, 13, 1008, 13, 8, 14, 1006, 14, 11, 104, 1, 99, 0, 0, 0];
+        let source = decompile(&program).source;
+        assert!(source.contains("if (mem[14]) {\n        output(1);\n    }"), "{}", source);
+    }
+
+    #[test]
+    fn while_loop_and_function_call() {
+        // main: rb = 100; f14(5); halt
+        // f14(n): while (n != 0) { output(n); n = n - 1 } return
+        let program = vec![
+            109, 100,
+            21101, 5, 0, 1,
+            21101, 13, 0, 0,
+            1105, 1, 14,
+            99,
+            109, 2,
+            21208, -1, 0, 1,
+            1205, 1, 32,
+            204, -1,
+            21201, -1, -1, -1,
+            1105, 1, 16,
+            109, -2,
+            2106, 0, 0,
+        ];
+        let decompiled = decompile(&program);
+        assert_eq!(vec![0, 14], decompiled.functions.iter().map(|f| f.entry).collect::<Vec<_>>());
+        assert_eq!(1, decompiled.functions[1].params);
+        let source = decompiled.source;
+        assert!(source.contains("    f14(5);\n    halt();"), "{}", source);
+        assert!(source.contains("    while (arg1 != 0) {\n        output(arg1);\n        arg1 = arg1 - 1;\n    }\n    return;"), "{}", source);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::analysis::Param;
+use crate::intcode::Amplifier;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+// What happens to the instruction pointer once a custom instruction ran.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Effect {
+    Continue,
+    Jump(usize),
+    Output(isize),
+    Halt,
+}
+
+// Read and Target parameters are passed as values, Write parameters as addresses.
+pub type Handler = Arc<dyn Fn(&mut Amplifier, &[isize]) -> Effect + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Opcode {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub handler: Handler,
+}
+
+pub trait Device {
+    // addresses are relative to the start of the mapped range
+    fn read(&mut self, offset: usize) -> isize;
+    fn write(&mut self, offset: usize, value: isize);
+}
+
+// shared with the host, so it can look at the device while the program runs
+pub type SharedDevice = Arc<Mutex<dyn Device + Send>>;
+
+// Custom opcodes and mapped devices of one Amplifier. Clones share the devices.
+#[derive(Clone, Default)]
+pub struct Registry {
+    opcodes: HashMap<usize, Opcode>,
+    devices: Vec<(Range<usize>, SharedDevice)>,
+}
+
+fn is_builtin(opcode: usize) -> bool {
+    (1..=9).contains(&opcode) || opcode == 99
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    // only 0 and 10 to 98 are free, instructions have at most 3 parameters
+    pub fn register(&mut self, opcode: usize, opcode_def: Opcode) {
+        if is_builtin(opcode) || opcode > 99 {
+            panic!("opcode {} can not be redefined", opcode);
+        }
+        if opcode_def.params.len() > 3 {
+            panic!("opcode {} has more than 3 parameters", opcode);
+        }
+        if self.opcodes.insert(opcode, opcode_def).is_some() {
+            panic!("opcode {} is already registered", opcode);
+        }
+    }
+
+    pub fn opcode(&self, opcode: usize) -> Option<&Opcode> {
+        self.opcodes.get(&opcode)
+    }
+
+    pub fn map(&mut self, range: Range<usize>, device: SharedDevice) {
+        if range.start >= range.end {
+            panic!("device mapped to empty range {:?}", range);
+        }
+        if let Some((other, _)) = self.devices.iter().find(|(r, _)| r.start < range.end && range.start < r.end) {
+            panic!("device range {:?} overlaps {:?}", range, other);
+        }
+        self.devices.push((range, device));
+    }
+
+    pub fn has_devices(&self) -> bool {
+        !self.devices.is_empty()
+    }
+
+    pub fn device(&self, address: usize) -> Option<(usize, &SharedDevice)> {
+        self.devices
+            .iter()
+            .find(|(range, _)| range.contains(&address))
+            .map(|(range, device)| (address - range.start, device))
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut opcodes: Vec<_> = self.opcodes.iter().map(|(code, op)| (*code, op.name.as_str())).collect();
+        opcodes.sort_unstable();
+        let devices: Vec<_> = self.devices.iter().map(|(range, _)| range).collect();
+        f.debug_struct("Registry").field("opcodes", &opcodes).field("devices", &devices).finish()
+    }
+}
+
+// Row major pixels, reading returns the current colour of a pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<isize>,
+    pub writes: usize,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer { width, height, pixels: vec![0; width * height], writes: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    pub fn render(&self) -> String {
+        let mut screen = String::new();
+        for row in self.pixels.chunks(self.width) {
+            screen.extend(row.iter().map(|p| if *p == 0 { '.' } else { '#' }));
+            screen.push('\n');
+        }
+        screen
+    }
+}
+
+impl Device for Framebuffer {
+    fn read(&mut self, offset: usize) -> isize {
+        self.pixels[offset]
+    }
+
+    fn write(&mut self, offset: usize, value: isize) {
+        self.writes += 1;
+        self.pixels[offset] = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::Param;
+    use crate::extension::{Device, Effect, Framebuffer};
+    use crate::intcode::Amplifier;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn custom_opcode() {
+        // 42: [c] = max(a, b) with the usual modes, then output [9]
+        let program = vec![1142, 3, 7, 9, 4, 9, 99, 0, 0, 0];
+        let mut amp = Amplifier::new(program, vec![]);
+        amp.register_opcode(42, "MAX", &[Param::Read, Param::Read, Param::Write], |amp, args| {
+            amp.store(args[2] as usize, args[0].max(args[1]));
+            Effect::Continue
+        });
+        assert_eq!(Some(7), amp.run_program(false));
+    }
+
+    #[test]
+    fn custom_output_and_jump() {
+        // 50 outputs its parameter doubled, 51 jumps to its parameter
+        let program = vec![10151, 4, 99, 99, 150, 21, 99];
+        let mut amp = Amplifier::new(program, vec![]);
+        amp.register_opcode(50, "DOUBLE", &[Param::Read], |_, args| Effect::Output(args[0] * 2));
+        amp.register_opcode(51, "JMP", &[Param::Target], |_, args| Effect::Jump(args[0] as usize));
+        assert_eq!(Some(42), amp.run_program_until_output(false));
+        assert_eq!(None, amp.run_program_until_output(false));
+    }
+
+    #[test]
+    #[should_panic(expected = "Illegal Opcode 0")]
+    fn opcode_zero_is_illegal() {
+        Amplifier::new(vec![0, 99], vec![]).run_program_until_output(false);
+    }
+
+    #[test]
+    #[should_panic(expected = "can not be redefined")]
+    fn builtins_are_reserved() {
+        Amplifier::new(vec![99], vec![]).register_opcode(4, "OUT", &[Param::Read], |_, _| Effect::Halt);
+    }
+
+    #[test]
+    fn framebuffer_device() {
+        // writes the input to pixels 1000 to 1003 of a 2x2 screen and reads one back
+        let program = vec![
+            3, 1000, 1101, 0, 1, 1001, 1101, 0, 1, 1003,
+            1002, 1000, 2, 1002, 4, 1002, 99,
+        ];
+        let screen = Arc::new(Mutex::new(Framebuffer::new(2, 2)));
+        let mut amp = Amplifier::new(program, vec![3]);
+        amp.map_device(1000..1004, screen.clone());
+        assert_eq!(Some(6), amp.run_program(false));
+        assert_eq!(vec![3, 1, 6, 1], screen.lock().unwrap().pixels);
+        assert_eq!(4, screen.lock().unwrap().writes);
+        assert_eq!(0, amp.get_program_clone()[1000]);
+        assert_eq!("##\n##\n", screen.lock().unwrap().render());
+    }
+
+    struct Counter(isize);
+
+    impl Device for Counter {
+        fn read(&mut self, _offset: usize) -> isize {
+            self.0 += 1;
+            self.0
+        }
+
+        fn write(&mut self, _offset: usize, value: isize) {
+            self.0 = value;
+        }
+    }
+
+    #[test]
+    fn reads_trigger_callbacks() {
+        // sets the counter to 10 and outputs two reads of it
+        let program = vec![1101, 0, 10, 500, 4, 500, 4, 500, 99];
+        let mut amp = Amplifier::new(program, vec![]);
+        amp.map_device(500..501, Arc::new(Mutex::new(Counter(0))));
+        assert_eq!(Some(11), amp.run_program_until_output(false));
+        assert_eq!(Some(12), amp.run_program_until_output(false));
+    }
+}
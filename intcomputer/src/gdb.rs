@@ -0,0 +1,587 @@
+use crate::intcode::{Amplifier, Event};
+use crate::watch::{Access, Hit, Watchpoint};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+// Minimal GDB remote serial protocol server.
+//
+// Target memory is byte addressed, every intcode word takes 8 little endian bytes, so word n
+// lives at address 8 * n. Register 0 is the instruction pointer as such a byte address,
+// register 1 the relative base in words. Outputs are forwarded as console output packets,
+// `monitor input <n>...` queues input for the program. Watchpoints cover whole words.
+pub const WORD: usize = 8;
+
+// stop signals
+const SIGTRAP: u8 = 5;
+const SIGILL: u8 = 4;
+// the program waits for input
+const SIGTTIN: u8 = 21;
+
+// served through qXfer so gdb knows both registers without an architecture of its own
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.intcode.core">
+    <reg name="ip" bitsize="64" type="code_ptr" regnum="0"/>
+    <reg name="rb" bitsize="64" type="int64" regnum="1"/>
+  </feature>
+</target>
+"#;
+
+// steps between checks for an interrupt from the client
+const POLL_INTERVAL: usize = 4096;
+
+pub struct Stub {
+    amp: Amplifier,
+    breakpoints: HashSet<usize>,
+    // vm watchpoint ids by gdb kind and word range
+    watchpoints: HashMap<(u8, usize, usize), Vec<usize>>,
+    no_ack: bool,
+    fault: Option<String>,
+}
+
+enum Stop {
+    Signal(u8),
+    Watch(Hit),
+    Exited,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn parse_hex(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 16).ok()
+}
+
+fn checksum(data: &str) -> u8 {
+    data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b))
+}
+
+enum Incoming {
+    Packet(String),
+    // 0x03 between packets
+    Interrupt,
+    // the checksum does not match, the client resends after a `-`
+    Corrupt,
+}
+
+// reads one packet, skipping acks
+fn read_packet<R: Read>(stream: &mut R) -> io::Result<Incoming> {
+    let mut byte = [0; 1];
+    loop {
+        stream.read_exact(&mut byte)?;
+        match byte[0] {
+            b'$' => break,
+            0x03 => return Ok(Incoming::Interrupt),
+            _ => (),
+        }
+    }
+    let mut data = Vec::new();
+    loop {
+        stream.read_exact(&mut byte)?;
+        if byte[0] == b'#' {
+            break;
+        }
+        data.push(byte[0]);
+    }
+    let mut sum = [0; 2];
+    stream.read_exact(&mut sum)?;
+    let data = String::from_utf8_lossy(&data).into_owned();
+    let expected = std::str::from_utf8(&sum).ok().and_then(|sum| u8::from_str_radix(sum, 16).ok());
+    if expected != Some(checksum(&data)) {
+        return Ok(Incoming::Corrupt);
+    }
+    Ok(Incoming::Packet(data))
+}
+
+pub fn write_packet<W: Write>(stream: &mut W, data: &str) -> io::Result<()> {
+    write!(stream, "${}#{:02x}", data, checksum(data))?;
+    stream.flush()
+}
+
+impl Stub {
+    pub fn new(amp: Amplifier) -> Stub {
+        Stub { amp, breakpoints: HashSet::new(), watchpoints: HashMap::new(), no_ack: false, fault: None }
+    }
+
+    pub fn amplifier(&self) -> &Amplifier {
+        &self.amp
+    }
+
+    // serves a single client, returns when it detaches or kills the program
+    pub fn serve(&mut self, listener: &TcpListener) -> io::Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        loop {
+            let packet = match read_packet(&mut stream) {
+                Ok(Incoming::Packet(packet)) => packet,
+                Ok(Incoming::Interrupt) => continue,
+                Ok(Incoming::Corrupt) => {
+                    if !self.no_ack {
+                        stream.write_all(b"-")?;
+                    }
+                    continue;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            if !self.no_ack {
+                stream.write_all(b"+")?;
+            }
+            let reply = match packet.as_bytes().first() {
+                Some(b'c') => self.resume(&mut stream, false)?,
+                Some(b's') => self.resume(&mut stream, true)?,
+                Some(b'k') => return Ok(()),
+                Some(b'D') => {
+                    write_packet(&mut stream, "OK")?;
+                    return Ok(());
+                }
+                _ => self.handle(&packet),
+            };
+            write_packet(&mut stream, &reply)?;
+        }
+    }
+
+    fn handle(&mut self, packet: &str) -> String {
+        let (command, args) = packet.split_at(1.min(packet.len()));
+        match command {
+            "?" => format!("S{:02x}", SIGTRAP),
+            "g" => self.registers(),
+            "G" => self.write_registers(args),
+            "p" => match parse_hex(args) {
+                Some(n) if n < 2 => self.registers()[n * 16..n * 16 + 16].to_string(),
+                _ => "E01".to_string(),
+            },
+            "P" => self.write_register(args),
+            "m" => self.read_memory(args),
+            "M" => self.write_memory(args),
+            "Z" | "z" => self.breakpoint(command == "Z", args),
+            "H" => "OK".to_string(),
+            "T" => "OK".to_string(),
+            "q" | "Q" => self.query(packet),
+            _ => String::new(),
+        }
+    }
+
+    fn query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            "PacketSize=4000;QStartNoAckMode+;swbreak+;qXfer:features:read+".to_string()
+        } else if packet == "QStartNoAckMode" {
+            self.no_ack = true;
+            "OK".to_string()
+        } else if packet == "qAttached" {
+            "1".to_string()
+        } else if packet == "qC" {
+            "QC1".to_string()
+        } else if packet == "qfThreadInfo" {
+            "m1".to_string()
+        } else if packet == "qsThreadInfo" {
+            "l".to_string()
+        } else if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            Stub::target_xml(args)
+        } else if let Some(command) = packet.strip_prefix("qRcmd,") {
+            let command = unhex(command).map(|c| String::from_utf8_lossy(&c).into_owned());
+            self.monitor(command.as_deref().unwrap_or(""))
+        } else {
+            String::new()
+        }
+    }
+
+    // m for a chunk with more to follow, l for the last one
+    fn target_xml(args: &str) -> String {
+        let (offset, len) = match Stub::range(args) {
+            Some(range) => range,
+            None => return "E01".to_string(),
+        };
+        let start = offset.min(TARGET_XML.len());
+        let end = start.saturating_add(len).min(TARGET_XML.len());
+        let more = if end < TARGET_XML.len() { "m" } else { "l" };
+        format!("{}{}", more, &TARGET_XML[start..end])
+    }
+
+    fn monitor(&mut self, command: &str) -> String {
+        let mut words = command.split_whitespace();
+        match words.next() {
+            Some("input") => {
+                let values: Result<Vec<isize>, _> = words.map(|w| w.parse()).collect();
+                match values {
+                    Ok(values) => {
+                        self.amp.push_input_vec(values);
+                        "OK".to_string()
+                    }
+                    Err(_) => hex(b"usage: monitor input <n>...\n"),
+                }
+            }
+            Some("fault") => hex(format!("{}\n", self.fault.as_deref().unwrap_or("no fault")).as_bytes()),
+            _ => hex(b"commands: input <n>..., fault\n"),
+        }
+    }
+
+    fn registers(&self) -> String {
+        let ip = (self.amp.ip() * WORD) as u64;
+        let rb = self.amp.relative_base() as i64;
+        hex(&ip.to_le_bytes()) + &hex(&rb.to_le_bytes())
+    }
+
+    fn word(bytes: &[u8]) -> i64 {
+        let mut word = [0; WORD];
+        word.copy_from_slice(bytes);
+        i64::from_le_bytes(word)
+    }
+
+    // ip is a byte address, only non negative multiples of WORD name a word
+    fn ip(bytes: &[u8]) -> Option<usize> {
+        usize::try_from(Stub::word(bytes)).ok().filter(|address| address % WORD == 0).map(|address| address / WORD)
+    }
+
+    fn write_registers(&mut self, args: &str) -> String {
+        match unhex(args) {
+            Some(ref bytes) if bytes.len() == 2 * WORD => match Stub::ip(&bytes[..WORD]) {
+                Some(ip) => {
+                    self.amp.set_ip(ip);
+                    self.amp.set_relative_base(Stub::word(&bytes[WORD..]) as isize);
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            },
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let mut parts = args.splitn(2, '=');
+        let n = parts.next().and_then(parse_hex);
+        let value = parts.next().and_then(unhex);
+        match (n, value) {
+            (Some(0), Some(ref bytes)) if bytes.len() == WORD => match Stub::ip(bytes) {
+                Some(ip) => self.amp.set_ip(ip),
+                None => return "E01".to_string(),
+            },
+            (Some(1), Some(ref bytes)) if bytes.len() == WORD => self.amp.set_relative_base(Stub::word(bytes) as isize),
+            _ => return "E01".to_string(),
+        }
+        "OK".to_string()
+    }
+
+    fn range(args: &str) -> Option<(usize, usize)> {
+        let mut parts = args.splitn(2, ',');
+        Some((parse_hex(parts.next()?)?, parse_hex(parts.next()?)?))
+    }
+
+    fn read_memory(&self, args: &str) -> String {
+        let (address, len) = match Stub::range(args) {
+            Some(range) => range,
+            None => return "E01".to_string(),
+        };
+        let bytes: Vec<u8> = (address..address.saturating_add(len))
+            .map_while(|a| self.amp.cell(a / WORD).map(|word| (word as i64).to_le_bytes()[a % WORD]))
+            .collect();
+        if bytes.is_empty() && len > 0 {
+            return "E14".to_string();
+        }
+        hex(&bytes)
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let mut parts = args.splitn(2, ':');
+        let range = parts.next().and_then(Stub::range);
+        let data = parts.next().and_then(unhex);
+        let ((address, len), data) = match (range, data) {
+            (Some(range), Some(data)) if data.len() == range.1 => (range, data),
+            _ => return "E01".to_string(),
+        };
+        let end = match address.checked_add(len) {
+            Some(end) => end,
+            None => return "E01".to_string(),
+        };
+        let memory = self.amp.memory_mut();
+        if end.div_ceil(WORD) > memory.len() {
+            return "E14".to_string();
+        }
+        for (i, byte) in data.iter().enumerate() {
+            let a = address + i;
+            let mut word = (memory[a / WORD] as i64).to_le_bytes();
+            word[a % WORD] = *byte;
+            memory[a / WORD] = i64::from_le_bytes(word) as isize;
+        }
+        "OK".to_string()
+    }
+
+    fn breakpoint(&mut self, insert: bool, args: &str) -> String {
+        let mut parts = args.split(',');
+        let kind = parts.next();
+        let address = parts.next().and_then(parse_hex);
+        match (kind, address) {
+            (Some("0"), Some(address)) | (Some("1"), Some(address)) if address % WORD == 0 => {
+                if insert {
+                    self.breakpoints.insert(address / WORD);
+                } else {
+                    self.breakpoints.remove(&(address / WORD));
+                }
+                "OK".to_string()
+            }
+            (Some("0"), Some(_)) | (Some("1"), Some(_)) => "E01".to_string(),
+            (Some(kind @ "2"), Some(address)) | (Some(kind @ "3"), Some(address)) | (Some(kind @ "4"), Some(address)) => {
+                let end = match parts.next().and_then(parse_hex) {
+                    Some(length) if length > 0 => address.checked_add(length),
+                    _ => None,
+                };
+                let end = match end {
+                    Some(end) => end,
+                    None => return "E01".to_string(),
+                };
+                let kind = kind.as_bytes()[0] - b'0';
+                let key = (kind, address / WORD, end.div_ceil(WORD));
+                self.watch(insert, key)
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn watch(&mut self, insert: bool, key: (u8, usize, usize)) -> String {
+        if !insert {
+            return match self.watchpoints.remove(&key) {
+                Some(ids) => {
+                    for id in ids {
+                        self.amp.unwatch(id);
+                    }
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            };
+        }
+        let (kind, start, end) = key;
+        let accesses: &[Access] = match kind {
+            2 => &[Access::Write],
+            3 => &[Access::Read],
+            _ => &[Access::Read, Access::Write],
+        };
+        let ids = accesses.iter().map(|access| self.amp.watch(Watchpoint::new(start..end, *access))).collect();
+        if let Some(old) = self.watchpoints.insert(key, ids) {
+            for id in old {
+                self.amp.unwatch(id);
+            }
+        }
+        "OK".to_string()
+    }
+
+    // the kind in the reply has to match the one gdb inserted
+    fn watch_kind(&self, hit: &Hit) -> &'static str {
+        let kind = self.watchpoints.iter().find(|(_, ids)| ids.contains(&hit.id)).map(|((kind, _, _), _)| *kind);
+        match kind {
+            Some(3) => "rwatch",
+            Some(4) => "awatch",
+            _ => "watch",
+        }
+    }
+
+    fn stop_reply(&self, stop: Stop) -> String {
+        match stop {
+            Stop::Signal(signal) => format!("S{:02x}", signal),
+            Stop::Watch(hit) => format!("T{:02x}{}:{:x};", SIGTRAP, self.watch_kind(&hit), hit.address * WORD),
+            Stop::Exited => "W00".to_string(),
+        }
+    }
+
+    fn interrupted(stream: &mut TcpStream) -> io::Result<bool> {
+        stream.set_nonblocking(true)?;
+        let mut byte = [0; 1];
+        let result = match stream.peek(&mut byte) {
+            Ok(n) if n > 0 && byte[0] == 0x03 => {
+                stream.read_exact(&mut byte)?;
+                Ok(true)
+            }
+            Ok(_) => Ok(false),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        };
+        stream.set_nonblocking(false)?;
+        result
+    }
+
+    // runs one instruction for a step, until a breakpoint or event otherwise
+    fn resume(&mut self, stream: &mut TcpStream, single: bool) -> io::Result<String> {
+        if self.fault.is_some() {
+            return Ok(self.stop_reply(Stop::Signal(SIGILL)));
+        }
+        let mut steps = 0;
+        loop {
+            let stop = match self.amp.step(false) {
+                Ok(Some(Event::Output(value))) => {
+                    write_packet(stream, &format!("O{}", hex(format!("{}\n", value).as_bytes())))?;
+                    None
+                }
+                Ok(Some(Event::NeedInput)) => Some(Stop::Signal(SIGTTIN)),
+                Ok(Some(Event::Halted)) => Some(Stop::Exited),
+                Ok(Some(Event::Watchpoint(hit))) => Some(Stop::Watch(hit)),
+                Ok(None) => None,
+                Err(fault) => {
+                    self.fault = Some(fault.to_string());
+                    Some(Stop::Signal(SIGILL))
+                }
+            };
+            if let Some(stop) = stop {
+                return Ok(self.stop_reply(stop));
+            }
+            steps += 1;
+            if single || self.breakpoints.contains(&self.amp.ip()) {
+                return Ok(self.stop_reply(Stop::Signal(SIGTRAP)));
+            }
+            if steps % POLL_INTERVAL == 0 && Stub::interrupted(stream)? {
+                return Ok(self.stop_reply(Stop::Signal(SIGTRAP)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gdb::{read_packet, write_packet, Incoming, Stub, WORD};
+    use crate::intcode::Amplifier;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    // stand-in for a GDB client
+    struct Client {
+        stream: TcpStream,
+    }
+
+    impl Client {
+        fn send(&mut self, packet: &str) -> String {
+            write_packet(&mut self.stream, packet).unwrap();
+            let mut ack = [0; 1];
+            self.stream.read_exact(&mut ack).unwrap();
+            assert_eq!(b'+', ack[0]);
+            self.reply()
+        }
+
+        fn reply(&mut self) -> String {
+            match read_packet(&mut self.stream).unwrap() {
+                Incoming::Packet(packet) => packet,
+                _ => panic!("no packet"),
+            }
+        }
+
+        fn ip(&mut self) -> usize {
+            let reply = self.send("p0");
+            let bytes: Vec<u8> = (0..WORD).map(|i| u8::from_str_radix(&reply[2 * i..2 * i + 2], 16).unwrap()).collect();
+            bytes.iter().rev().fold(0, |ip, b| ip * 256 + *b as usize) / WORD
+        }
+    }
+
+    fn start(program: Vec<isize>) -> (Client, thread::JoinHandle<Stub>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut stub = Stub::new(Amplifier::new(program, vec![]));
+            stub.serve(&listener).unwrap();
+            stub
+        });
+        (Client { stream: TcpStream::connect(address).unwrap() }, server)
+    }
+
+    #[test]
+    fn step_break_continue() {
+        // [20] = input * 3, output it twice, halt
+        let (mut client, server) = start(vec![3, 20, 1002, 20, 3, 20, 4, 20, 4, 20, 99]);
+        assert!(client.send("qSupported:swbreak+").contains("PacketSize"));
+        assert_eq!("S05", client.send("?"));
+        assert_eq!("0000000000000000", client.send("p1"));
+
+        // waits for input, then gets some through the monitor
+        assert_eq!("S15", client.send("c"));
+        assert_eq!("OK", client.send(&format!("qRcmd,{}", super::hex(b"input 7"))));
+        assert_eq!("S05", client.send("s"));
+        assert_eq!(2, client.ip());
+
+        assert_eq!("OK", client.send(&format!("Z0,{:x},1", 8 * WORD)));
+        assert_eq!(format!("O{}", super::hex(b"21\n")), client.send("c"));
+        assert_eq!("S05", client.reply());
+        assert_eq!(8, client.ip());
+        assert_eq!("1500000000000000", client.send(&format!("m{:x},8", 20 * WORD)));
+
+        assert_eq!("OK", client.send(&format!("M{:x},1:02", 20 * WORD)));
+        assert_eq!("OK", client.send(&format!("z0,{:x},1", 8 * WORD)));
+        assert_eq!(format!("O{}", super::hex(b"2\n")), client.send("c"));
+        assert_eq!("W00", client.reply());
+        client.stream.write_all(b"$k#6b").unwrap();
+        let stub = server.join().unwrap();
+        assert_eq!(2, stub.amplifier().memory()[20]);
+    }
+
+    #[test]
+    fn watchpoints() {
+        let (mut client, server) = start(vec![3, 20, 1002, 20, 3, 20, 4, 20, 4, 20, 99]);
+        let cell = 20 * WORD;
+        assert_eq!("OK", client.send(&format!("Z2,{:x},8", cell)));
+        assert_eq!("S15", client.send("c"));
+        assert_eq!("OK", client.send(&format!("qRcmd,{}", super::hex(b"input 7"))));
+        assert_eq!(format!("T05watch:{:x};", cell), client.send("c"));
+        assert_eq!(2, client.ip());
+        assert_eq!(format!("T05watch:{:x};", cell), client.send("c"));
+        assert_eq!(6, client.ip());
+
+        // the output of the reading instruction comes before the stop
+        assert_eq!("OK", client.send(&format!("z2,{:x},8", cell)));
+        assert_eq!("E01", client.send(&format!("z2,{:x},8", cell)));
+        assert_eq!("OK", client.send(&format!("Z3,{:x},1", cell + 4)));
+        assert_eq!(format!("O{}", super::hex(b"21\n")), client.send("c"));
+        assert_eq!(format!("T05rwatch:{:x};", cell), client.reply());
+        client.stream.write_all(b"$k#6b").unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn corrupt_packets_and_overflowing_ranges() {
+        let (mut client, server) = start(vec![99]);
+        client.stream.write_all(b"$?#00").unwrap();
+        let mut nak = [0; 1];
+        client.stream.read_exact(&mut nak).unwrap();
+        assert_eq!(b'-', nak[0]);
+        assert_eq!("S05", client.send("?"));
+
+        assert_eq!("E01", client.send(&format!("M{:x},2:0000", usize::MAX)));
+        assert_eq!("E01", client.send(&format!("Z2,{:x},8", usize::MAX - 3)));
+        client.stream.write_all(b"$k#6b").unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn target_description_and_register_checks() {
+        let (mut client, server) = start(vec![1101, 1, 2, 20, 99]);
+        assert!(client.send("qSupported:xmlRegisters=i386").contains("qXfer:features:read+"));
+        let mut xml = String::new();
+        loop {
+            let reply = client.send(&format!("qXfer:features:read:target.xml:{:x},40", xml.len()));
+            xml += &reply[1..];
+            if reply.starts_with('l') {
+                break;
+            }
+            assert!(reply.starts_with('m'));
+        }
+        assert!(xml.contains(r#"<reg name="ip" bitsize="64" type="code_ptr" regnum="0"/>"#));
+        assert!(xml.contains(r#"<reg name="rb" bitsize="64" type="int64" regnum="1"/>"#));
+        assert_eq!("l", client.send(&format!("qXfer:features:read:target.xml:{:x},40", usize::MAX)));
+
+        // negative and misaligned instruction pointers are refused and leave ip alone
+        assert_eq!("E01", client.send(&format!("P0={}", "ff".repeat(WORD))));
+        assert_eq!("E01", client.send("P0=0300000000000000"));
+        assert_eq!("E01", client.send(&format!("G{}{}", "ff".repeat(WORD), "00".repeat(WORD))));
+        assert_eq!(0, client.ip());
+        assert_eq!("OK", client.send("P0=2000000000000000"));
+        assert_eq!(4, client.ip());
+        client.stream.write_all(b"$k#6b").unwrap();
+        server.join().unwrap();
+    }
+}
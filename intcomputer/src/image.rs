@@ -0,0 +1,220 @@
+use crate::loader::{self, Error};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+// Binary image layout, all header fields little endian:
+//   0  magic "ICIM"
+//   4  version
+//   5  word width in bits
+//   6  reserved, zero
+//   8  entry point
+//  16  memory size hint in words
+//  24  checksum of entry point, memory size hint and the words (loader::checksum)
+//  32  number of words
+//  40  words as zigzag LEB128 varints
+pub const MAGIC: [u8; 4] = *b"ICIM";
+pub const VERSION: u8 = 2;
+pub const WIDTH: u8 = 64;
+// the largest memory size hint accepted, in words
+pub const MAX_MEMORY: usize = 1 << 24;
+const HEADER_LEN: usize = 40;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub entry: usize,
+    pub memory_hint: usize,
+    pub program: Vec<isize>,
+}
+
+impl Image {
+    pub fn new(program: Vec<isize>) -> Image {
+        Image { entry: 0, memory_hint: program.len(), program }
+    }
+}
+
+// the hint has to cover the program and stay below MAX_MEMORY, the entry point has to lie in it
+pub fn validate(image: &Image) -> Result<(), Error> {
+    if image.memory_hint < image.program.len() || image.memory_hint > MAX_MEMORY {
+        return Err(Error::MemoryHint(image.memory_hint));
+    }
+    if image.entry >= image.memory_hint {
+        return Err(Error::Entry(image.entry));
+    }
+    Ok(())
+}
+
+fn checksum(entry: usize, memory_hint: usize, program: &[isize]) -> u64 {
+    loader::checksum(&[&[entry as isize, memory_hint as isize], program].concat())
+}
+
+pub fn is_image(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+pub fn encode(image: &Image) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + image.program.len() * 2);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(VERSION);
+    bytes.push(WIDTH);
+    bytes.extend_from_slice(&[0, 0]);
+    bytes.extend_from_slice(&(image.entry as u64).to_le_bytes());
+    bytes.extend_from_slice(&(image.memory_hint as u64).to_le_bytes());
+    bytes.extend_from_slice(&checksum(image.entry, image.memory_hint, &image.program).to_le_bytes());
+    bytes.extend_from_slice(&(image.program.len() as u64).to_le_bytes());
+    for value in image.program.iter() {
+        let mut rest = zigzag(*value as i64);
+        while rest >= 0x80 {
+            bytes.push(rest as u8 | 0x80);
+            rest >>= 7;
+        }
+        bytes.push(rest as u8);
+    }
+    bytes
+}
+
+fn field(bytes: &[u8], offset: usize) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(word)
+}
+
+fn word(value: u64) -> Result<usize, Error> {
+    usize::try_from(value).map_err(|_| Error::Overlong)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Image, Error> {
+    if bytes.len() < HEADER_LEN || !is_image(bytes) {
+        return Err(Error::Truncated);
+    }
+    if bytes[4] != VERSION {
+        return Err(Error::UnsupportedVersion(bytes[4]));
+    }
+    if bytes[5] != WIDTH {
+        return Err(Error::UnsupportedWidth(bytes[5]));
+    }
+    let entry = word(field(bytes, 8))?;
+    let memory_hint = word(field(bytes, 16))?;
+    let expected = field(bytes, 24);
+    let len = word(field(bytes, 32))?;
+
+    let mut program = Vec::with_capacity(len.min(bytes.len()));
+    let mut body = bytes[HEADER_LEN..].iter();
+    for _ in 0..len {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = *body.next().ok_or(Error::Truncated)?;
+            if shift == 63 && byte > 1 {
+                return Err(Error::Overlong);
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        program.push(isize::try_from(unzigzag(value)).map_err(|_| Error::Overlong)?);
+    }
+    if !body.as_slice().is_empty() {
+        return Err(Error::Trailing(body.as_slice().len()));
+    }
+
+    let actual = checksum(entry, memory_hint, &program);
+    if actual != expected {
+        return Err(Error::Checksum { expected, actual });
+    }
+    let image = Image { entry, memory_hint, program };
+    validate(&image)?;
+    Ok(image)
+}
+
+// reads either format, text programs get entry 0 and their own length as hint
+pub fn read<P: AsRef<Path>>(path: P) -> Result<Image, Error> {
+    loader::Loader::new().load_image(&fs::read(path)?)
+}
+
+pub fn to_text(program: &[isize]) -> String {
+    let values: Vec<String> = program.iter().map(|v| v.to_string()).collect();
+    format!("{}\n", values.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image::{decode, encode, to_text, Image};
+    use crate::intcode::Amplifier;
+    use crate::loader::{self, Error, Loader};
+
+    #[test]
+    fn round_trip() {
+        let program = crate::intcode::read_data("9");
+        let image = Image { entry: 3, memory_hint: 4096, program: program.clone() };
+        let bytes = encode(&image);
+        assert!(bytes.len() < to_text(&program).len());
+        assert_eq!(image, decode(&bytes).unwrap());
+    }
+
+    #[test]
+    fn extreme_values() {
+        let image = Image::new(vec![0, -1, 1, 63, -64, 64, isize::MAX, isize::MIN]);
+        assert_eq!(image, decode(&encode(&image)).unwrap());
+    }
+
+    #[test]
+    fn corruption_is_detected() {
+        let mut bytes = encode(&Image::new(vec![1, 0, 0, 0, 99]));
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(matches!(decode(&bytes), Err(Error::Checksum { .. })));
+        assert!(matches!(decode(&bytes[..last]), Err(Error::Truncated)));
+        bytes[4] = 9;
+        assert!(matches!(decode(&bytes), Err(Error::UnsupportedVersion(9))));
+    }
+
+    #[test]
+    fn header_is_checked() {
+        let mut bytes = encode(&Image::new(vec![1, 0, 0, 0, 99]));
+        bytes[16] ^= 1;
+        assert!(matches!(decode(&bytes), Err(Error::Checksum { .. })));
+
+        let program = vec![1, 0, 0, 0, 99];
+        let huge = Image { entry: 0, memory_hint: usize::MAX, program: program.clone() };
+        assert!(matches!(decode(&encode(&huge)), Err(Error::MemoryHint(usize::MAX))));
+        let short = Image { entry: 0, memory_hint: 4, program: program.clone() };
+        assert!(matches!(decode(&encode(&short)), Err(Error::MemoryHint(4))));
+        let outside = Image { entry: 5, memory_hint: 5, program };
+        assert!(matches!(decode(&encode(&outside)), Err(Error::Entry(5))));
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected() {
+        let mut bytes = encode(&Image::new(vec![1, 0, 0, 0, 99]));
+        bytes.extend_from_slice(&[0, 0]);
+        assert!(matches!(decode(&bytes), Err(Error::Trailing(2))));
+    }
+
+    #[test]
+    fn amplifier_starts_at_entry() {
+        // 0: out 0, 3: out 99, 5: halt
+        let image = Image { entry: 3, memory_hint: 100_000, program: vec![104, 0, 99, 104, 99, 99] };
+        let mut amp = Amplifier::from_image(decode(&encode(&image)).unwrap(), vec![]);
+        assert_eq!(Some(99), amp.run_program_until_output(false));
+        assert_eq!(100_000, amp.get_program_clone().len());
+    }
+
+    #[test]
+    fn loader_detects_format() {
+        let program = vec![1101, 2, 3, 5, 99, 0];
+        let loader = Loader { checksum: Some(loader::checksum(&program)), ..Loader::new() };
+        assert_eq!(program, loader.load_bytes(&encode(&Image::new(program.clone()))).unwrap());
+        assert_eq!(program, loader.load_bytes(to_text(&program).as_bytes()).unwrap());
+    }
+}
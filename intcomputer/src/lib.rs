@@ -1,20 +1,56 @@
+pub mod analysis;
+pub mod conformance;
+pub mod decompile;
+pub mod extension;
+pub mod gdb;
+pub mod image;
+pub mod loader;
+pub mod patch;
+pub mod process;
+pub mod search;
+pub mod shell;
+pub mod symbolic;
+pub mod terminal;
+pub mod translate;
+pub mod watch;
+
 pub mod intcode {
+    use crate::analysis::Param;
+    use crate::extension::{Effect, Opcode, Registry, SharedDevice};
+    use crate::image::Image;
+    use crate::patch::{self, Patch};
+    use crate::watch::{Access, Hit, Watchpoint, Watchpoints};
+    use std::borrow::Cow;
     use std::collections::VecDeque;
     use std::convert::TryFrom;
-    use std::fs;
+    use std::error;
+    use std::fmt;
     use std::io;
+    use std::ops::Range;
+    use std::sync::Arc;
+
+    // zero words after the program that new and from_static provide
+    pub(crate) const PADDING: usize = 4 * 1024 * 10;
+
     #[derive(Debug, Clone)]
     pub struct Amplifier {
         ip: usize,
         rb: isize,
         network_mode_enabled: bool,
         inputbuffer: VecDeque<isize>,
-        program: Vec<isize>,
+        // borrowed for programs embedded with from_static until the first write
+        program: Cow<'static, [isize]>,
+        // zero words behind the program that are not allocated yet
+        padding: usize,
+        registry: Registry,
+        profile: Profile,
+        watchpoints: Watchpoints,
+        // hit of a pausing watchpoint that still has to be reported
+        paused: Option<Hit>,
     }
 
     #[derive(Copy, Clone)]
     pub enum Instruction {
-        NOOP,
         ADD(usize, usize, usize),
         MULT(usize, usize, usize),
         READ(usize),
@@ -25,12 +61,13 @@ pub mod intcode {
         EQUALS(usize, usize, usize),
         ADJUSTRB(usize),
         HALT,
+        // opcode registered with Amplifier::register_opcode
+        EXTENSION(usize),
     }
 
     impl Instruction {
         fn first(self) -> usize {
             match self {
-                Instruction::NOOP => panic!("NOOP has no first parameter"),
                 Instruction::ADD(a, _, _) => a,
                 Instruction::MULT(a, _, _) => a,
                 Instruction::READ(_) => panic!("READ has no first parameter"),
@@ -41,11 +78,11 @@ pub mod intcode {
                 Instruction::EQUALS(a, _, _) => a,
                 Instruction::ADJUSTRB(a) => a,
                 Instruction::HALT => panic!("HALT should never run"),
+                Instruction::EXTENSION(_) => panic!("extensions decode their own parameters"),
             }
         }
         fn second(self) -> usize {
             match self {
-                Instruction::NOOP => panic!("NOOP has no first parameter"),
                 Instruction::ADD(_, b, _) => b,
                 Instruction::MULT(_, b, _) => b,
                 Instruction::READ(_) => panic!("READ has no second parameter"),
@@ -56,11 +93,11 @@ pub mod intcode {
                 Instruction::EQUALS(_, b, _) => b,
                 Instruction::ADJUSTRB(_) => panic!("ADJUSTRB has no second parameter"),
                 Instruction::HALT => panic!("HALT should never run"),
+                Instruction::EXTENSION(_) => panic!("extensions decode their own parameters"),
             }
         }
         fn target(self) -> usize {
             match self {
-                Instruction::NOOP => panic!("NOOP has no target"),
                 Instruction::ADD(_, _, c) => c,
                 Instruction::MULT(_, _, c) => c,
                 Instruction::READ(a) => a,
@@ -71,24 +108,79 @@ pub mod intcode {
                 Instruction::EQUALS(_, _, c) => c,
                 Instruction::ADJUSTRB(_) => panic!("ADJUSTRB has no target"),
                 Instruction::HALT => panic!("HALT should never run"),
+                Instruction::EXTENSION(_) => panic!("extensions decode their own parameters"),
             }
         }
     }
 
-    pub fn read_data(file_name: &str) -> Vec<isize> {
-        let mut program: Vec<isize> = Vec::new();
-        let data = fs::read_to_string(file_name).expect("Something went wrong reading the file");
-        for line in data.split(",") {
-            match line.parse::<isize>() {
-                Ok(x) => program.push(x),
-                Err(_) => (),
-            };
+    // The instructions known after each puzzle that extended the computer.
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    pub enum Profile {
+        // ADD, MULT, HALT
+        Day2,
+        // plus I/O, jumps and comparisons
+        Day5,
+        // plus the relative base
+        Day9,
+    }
+
+    impl Profile {
+        pub fn allows(self, opcode: usize) -> bool {
+            match self {
+                Profile::Day2 => opcode == 1 || opcode == 2 || opcode == 99,
+                Profile::Day5 => (1..=8).contains(&opcode) || opcode == 99,
+                Profile::Day9 => (1..=9).contains(&opcode) || opcode == 99,
+            }
         }
-        program
     }
 
-    fn conv(x: isize) -> usize {
-        usize::try_from(x).expect("negative instruction pointer")
+    // address is the instruction pointer of the faulting instruction
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Fault {
+        IllegalOpcode { address: usize, opcode: usize },
+        NotInProfile { address: usize, opcode: usize, profile: Profile },
+        InvalidMode { address: usize, mode: usize },
+        ImmediateWrite { address: usize },
+        NegativeAddress { address: usize, value: isize },
+        OutOfBounds { address: usize, index: usize },
+        Overflow { address: usize },
+    }
+
+    impl fmt::Display for Fault {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Fault::IllegalOpcode { address, opcode } => write!(f, "Illegal Opcode {} at {}", opcode, address),
+                Fault::NotInProfile { address, opcode, profile } =>
+                    write!(f, "opcode {} at {} is not part of the {:?} instruction set", opcode, address, profile),
+                Fault::InvalidMode { address, mode } => write!(f, "invalid mode {} at {}", mode, address),
+                Fault::ImmediateWrite { address } =>
+                    write!(f, "Parameters that an instruction writes to must never be in immediate mode. (at {})", address),
+                Fault::NegativeAddress { address, value } => write!(f, "negative address {} at {}", value, address),
+                Fault::OutOfBounds { address, index } => write!(f, "address {} out of bounds at {}", index, address),
+                Fault::Overflow { address } => write!(f, "arithmetic overflow at {}", address),
+            }
+        }
+    }
+
+    impl error::Error for Fault {}
+
+    // Reasons for run_until_event to hand control back to the caller.
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    pub enum Event {
+        Output(isize),
+        // the input buffer is empty, ip still points at the READ
+        NeedInput,
+        Halted,
+        // a watchpoint paused the vm after the instruction that triggered it
+        Watchpoint(Hit),
+    }
+
+    // panics with the loader's diagnostic, use loader::load to handle errors
+    pub fn read_data(file_name: &str) -> Vec<isize> {
+        match crate::loader::load(file_name) {
+            Ok(program) => program,
+            Err(e) => panic!("{}: {}", file_name, e),
+        }
     }
 
     fn get_opcode(mut value: usize) -> usize {
@@ -137,25 +229,53 @@ pub mod intcode {
         }
     }
 
+    // isize and i64 are the same on 64 bit targets, other targets get a copy
+    #[cfg(target_pointer_width = "64")]
+    fn static_words(program: &'static [i64]) -> Cow<'static, [isize]> {
+        Cow::Borrowed(unsafe { std::slice::from_raw_parts(program.as_ptr() as *const isize, program.len()) })
+    }
+
+    #[cfg(not(target_pointer_width = "64"))]
+    fn static_words(program: &'static [i64]) -> Cow<'static, [isize]> {
+        Cow::Owned(program.iter().map(|value| *value as isize).collect())
+    }
+
     impl Amplifier {
         pub fn new(program: Vec<isize>, input: Vec<isize>) -> Amplifier {
-            let mut temp = Amplifier {
-                inputbuffer: VecDeque::from(input),
-                program: program,
-                network_mode_enabled: false,
-                rb: 0,
-                ip: 0,
-            };
-            temp.program.append(&mut vec![0; 4 * 1024 * 10]);
+            let mut temp = Amplifier::new_test(program, input);
+            temp.program.to_mut().append(&mut vec![0; PADDING]);
+            temp
+        }
+
+        // for programs embedded with include_intcode!, memory is copied on the first write
+        pub fn from_static(program: &'static [i64], input: Vec<isize>) -> Amplifier {
+            let mut temp = Amplifier::new_test(Vec::new(), input);
+            temp.program = static_words(program);
+            temp.padding = PADDING;
+            temp
+        }
+
+        // starts at the image's entry point, the memory hint can only add to the usual padding
+        pub fn from_image(image: Image, input: Vec<isize>) -> Amplifier {
+            let len = image.program.len();
+            let mut temp = Amplifier::new_test(image.program, input);
+            temp.padding = PADDING.max(image.memory_hint.saturating_sub(len));
+            temp.ip = image.entry;
             temp
         }
+
         pub fn new_test(program: Vec<isize>, input: Vec<isize>) -> Amplifier {
             Amplifier {
                 inputbuffer: VecDeque::from(input),
-                program: program,
+                program: Cow::Owned(program),
+                padding: 0,
                 network_mode_enabled: false,
                 rb: 0,
                 ip: 0,
+                registry: Registry::new(),
+                profile: Profile::Day9,
+                watchpoints: Watchpoints::default(),
+                paused: None,
             }
         }
 
@@ -163,6 +283,10 @@ pub mod intcode {
             self.network_mode_enabled = new_setting;
         }
 
+        pub fn set_profile(&mut self, profile: Profile) {
+            self.profile = profile;
+        }
+
         pub fn push_input(&mut self, input: isize) {
             self.inputbuffer.push_back(input);
         }
@@ -175,11 +299,146 @@ pub mod intcode {
             self.inputbuffer.append(&mut input);
         }
 
-        fn get_access_index(&self, mode: Mode, index: usize) -> usize {
-            match mode {
-                Mode::Position => conv(self.program[index]),
-                Mode::Immediate => index,
-                Mode::Relative => conv(self.program[index] + self.rb),
+        pub fn register_opcode<F>(&mut self, opcode: usize, name: &str, params: &[Param], handler: F)
+        where
+            F: Fn(&mut Amplifier, &[isize]) -> Effect + Send + Sync + 'static,
+        {
+            let opcode_def = Opcode { name: name.to_string(), params: params.to_vec(), handler: Arc::new(handler) };
+            self.registry.register(opcode, opcode_def);
+        }
+
+        // reads and writes of the range go to the device instead of memory
+        pub fn map_device(&mut self, range: Range<usize>, device: SharedDevice) {
+            self.registry.map(range, device);
+        }
+
+        pub fn watch(&mut self, watchpoint: Watchpoint) -> usize {
+            self.watchpoints.add(watchpoint)
+        }
+
+        pub fn unwatch(&mut self, id: usize) -> bool {
+            self.watchpoints.remove(id)
+        }
+
+        pub fn load(&self, address: usize) -> isize {
+            self.read_memory(address).unwrap_or_else(|fault| panic!("{}", fault))
+        }
+
+        pub fn store(&mut self, address: usize, value: isize) {
+            self.write_memory(address, value).unwrap_or_else(|fault| panic!("{}", fault))
+        }
+
+        fn read_memory(&self, index: usize) -> Result<isize, Fault> {
+            if self.registry.has_devices() {
+                if let Some((offset, device)) = self.registry.device(index) {
+                    return Ok(device.lock().unwrap().read(offset));
+                }
+            }
+            self.cell(index).ok_or(Fault::OutOfBounds { address: self.ip, index })
+        }
+
+        // words of memory including the padding, whether it is allocated yet or not
+        pub fn memory_size(&self) -> usize {
+            self.program.len() + self.padding
+        }
+
+        // raw read for debuggers, bypasses mapped devices and watchpoints
+        pub fn cell(&self, index: usize) -> Option<isize> {
+            match self.program.get(index) {
+                Some(value) => Some(*value),
+                None if index < self.memory_size() => Some(0),
+                None => None,
+            }
+        }
+
+        // copies a borrowed program and allocates the padding
+        fn materialize(&mut self) -> &mut Vec<isize> {
+            let size = self.memory_size();
+            self.padding = 0;
+            let program = self.program.to_mut();
+            program.resize(size, 0);
+            program
+        }
+
+        // the nth parameter of the current instruction, immediate ones are fetched like the opcode
+        fn read_param(&mut self, n: usize, index: usize) -> Result<isize, Fault> {
+            match self.mode(n)? {
+                Mode::Immediate => self.fetch(index),
+                _ => self.read_operand(index),
+            }
+        }
+
+        // reads done by instructions, unlike load these trigger watchpoints
+        fn read_operand(&mut self, index: usize) -> Result<isize, Fault> {
+            let value = self.read_memory(index)?;
+            if !self.watchpoints.is_empty() {
+                self.check_watchpoints(index, Access::Read, None, value);
+            }
+            Ok(value)
+        }
+
+        fn check_watchpoints(&mut self, index: usize, access: Access, old: Option<isize>, new: isize) {
+            if let Some(hit) = self.watchpoints.check(self.ip, index, access, old, new) {
+                self.paused.get_or_insert(hit);
+            }
+        }
+
+        fn write_memory(&mut self, index: usize, value: isize) -> Result<(), Fault> {
+            if !self.watchpoints.is_empty() {
+                let old = match self.registry.device(index) {
+                    Some(_) => None,
+                    None => self.cell(index),
+                };
+                self.check_watchpoints(index, Access::Write, old, value);
+            }
+            if self.registry.has_devices() {
+                if let Some((offset, device)) = self.registry.device(index) {
+                    device.lock().unwrap().write(offset, value);
+                    return Ok(());
+                }
+            }
+            if index >= self.memory_size() {
+                return Err(Fault::OutOfBounds { address: self.ip, index });
+            }
+            self.materialize()[index] = value;
+            Ok(())
+        }
+
+        // instructions are always fetched from memory, never from devices
+        fn fetch(&self, index: usize) -> Result<isize, Fault> {
+            self.cell(index).ok_or(Fault::OutOfBounds { address: self.ip, index })
+        }
+
+        fn checked(&self, value: Option<isize>) -> Result<isize, Fault> {
+            value.ok_or(Fault::Overflow { address: self.ip })
+        }
+
+        fn conv(&self, value: isize) -> Result<usize, Fault> {
+            usize::try_from(value).map_err(|_| Fault::NegativeAddress { address: self.ip, value })
+        }
+
+        // mode of the nth parameter, counted from 0
+        fn mode(&self, n: usize) -> Result<Mode, Fault> {
+            let mut value = self.conv(self.fetch(self.ip)?)? / 100;
+            for _ in 0..n {
+                value /= 10;
+            }
+            match value % 10 {
+                0 => Ok(Mode::Position),
+                1 => Ok(Mode::Immediate),
+                2 => Ok(Mode::Relative),
+                mode => Err(Fault::InvalidMode { address: self.ip, mode }),
+            }
+        }
+
+        // index of the nth parameter, counted from 0
+        fn get_access_index(&self, n: usize, param: Param) -> Result<usize, Fault> {
+            let index = self.ip + 1 + n;
+            match self.mode(n)? {
+                Mode::Position => self.conv(self.fetch(index)?),
+                Mode::Immediate if param == Param::Write => Err(Fault::ImmediateWrite { address: self.ip }),
+                Mode::Immediate => Ok(index),
+                Mode::Relative => self.conv(self.checked(self.fetch(index)?.checked_add(self.rb))?),
             }
         }
 
@@ -192,7 +451,7 @@ pub mod intcode {
                 } else {
                     print!("{}, ", item);
                 }
-                if *item == 0 as isize {
+                if *item == 0 {
                     if was_zero == 50{
                         break;
                     } else {
@@ -205,58 +464,46 @@ pub mod intcode {
             println!("]");
         }
 
-        fn parse_instruction(&self) -> Instruction {
-            let opcode = get_opcode(conv(self.program[self.ip]));
-            let modes = get_modes(conv(self.program[self.ip]));
+        fn parse_instruction(&self) -> Result<Instruction, Fault> {
+            let opcode = get_opcode(self.conv(self.fetch(self.ip)?)?);
+            if self.registry.opcode(opcode).is_some() {
+                return Ok(Instruction::EXTENSION(opcode));
+            }
+            if crate::analysis::params(opcode as isize).is_none() {
+                return Err(Fault::IllegalOpcode { address: self.ip, opcode });
+            }
+            if !self.profile.allows(opcode) {
+                return Err(Fault::NotInProfile { address: self.ip, opcode, profile: self.profile });
+            }
+            let read = |n| self.get_access_index(n, Param::Read);
+            let write = |n| self.get_access_index(n, Param::Write);
             let inst = match opcode {
-                0 => Instruction::NOOP,
-                1 => Instruction::ADD(
-                    self.get_access_index(modes.mode1, self.ip + 1),
-                    self.get_access_index(modes.mode2, self.ip + 2),
-                    self.get_access_index(modes.mode3, self.ip + 3),
-                ),
-                2 => Instruction::MULT(
-                    self.get_access_index(modes.mode1, self.ip + 1),
-                    self.get_access_index(modes.mode2, self.ip + 2),
-                    self.get_access_index(modes.mode3, self.ip + 3),
-                ),
-                3 => Instruction::READ(self.get_access_index(modes.mode1, self.ip + 1)),
-                4 => Instruction::WRITE(self.get_access_index(modes.mode1, self.ip + 1)),
-                5 => Instruction::JUMPIFTRUE(
-                    self.get_access_index(modes.mode1, self.ip + 1),
-                    self.get_access_index(modes.mode2, self.ip + 2),
-                ),
-                6 => Instruction::JUMPIFFALSE(
-                    self.get_access_index(modes.mode1, self.ip + 1),
-                    self.get_access_index(modes.mode2, self.ip + 2),
-                ),
-                7 => Instruction::LESSTHAN(
-                    self.get_access_index(modes.mode1, self.ip + 1),
-                    self.get_access_index(modes.mode2, self.ip + 2),
-                    self.get_access_index(modes.mode3, self.ip + 3),
-                ),
-                8 => Instruction::EQUALS(
-                    self.get_access_index(modes.mode1, self.ip + 1),
-                    self.get_access_index(modes.mode2, self.ip + 2),
-                    self.get_access_index(modes.mode3, self.ip + 3),
-                ),
-                9 => Instruction::ADJUSTRB(self.get_access_index(modes.mode1, self.ip + 1)),
-                99 => Instruction::HALT,
-                a => panic!("Illegal Opcode {}", a),
+                1 => Instruction::ADD(read(0)?, read(1)?, write(2)?),
+                2 => Instruction::MULT(read(0)?, read(1)?, write(2)?),
+                3 => Instruction::READ(write(0)?),
+                4 => Instruction::WRITE(read(0)?),
+                5 => Instruction::JUMPIFTRUE(read(0)?, read(1)?),
+                6 => Instruction::JUMPIFFALSE(read(0)?, read(1)?),
+                7 => Instruction::LESSTHAN(read(0)?, read(1)?, write(2)?),
+                8 => Instruction::EQUALS(read(0)?, read(1)?, write(2)?),
+                9 => Instruction::ADJUSTRB(read(0)?),
+                _ => Instruction::HALT,
             };
-            inst
+            Ok(inst)
         }
 
-        pub fn add(&mut self, inst: Instruction, debug: bool) {
+        fn add(&mut self, inst: Instruction, debug: bool) -> Result<(), Fault> {
+            let (a, b) = (self.read_param(0, inst.first())?, self.read_param(1, inst.second())?);
+            let sum = self.checked(a.checked_add(b))?;
             if debug {
-                println!("[{}] := [{}] + [{}]\n {} = {} + {}", inst.target(), inst.first(), inst.second(),
-                self.program[inst.first()] + self.program[inst.second()], self.program[inst.first()], self.program[inst.second()]);
+                println!("[{}] := [{}] + [{}]\n {} = {} + {}", inst.target(), inst.first(), inst.second(), sum, a, b);
             };
-            self.program[inst.target()] = self.program[inst.first()] + self.program[inst.second()];
+            self.write_memory(inst.target(), sum)?;
             self.ip += 4;
+            Ok(())
         }
 
-        pub fn mult(&mut self, inst: Instruction, debug: bool) {
+        fn mult(&mut self, inst: Instruction, debug: bool) -> Result<(), Fault> {
             if debug {
                 println!(
                     "MULT {}, {}, {}",
@@ -265,275 +512,274 @@ pub mod intcode {
                     inst.second()
                 );
             };
-            self.program[inst.target()] = self.program[inst.first()] * self.program[inst.second()];
+            let (a, b) = (self.read_param(0, inst.first())?, self.read_param(1, inst.second())?);
+            let product = self.checked(a.checked_mul(b))?;
+            self.write_memory(inst.target(), product)?;
             self.ip += 4;
+            Ok(())
         }
 
-        fn read(&mut self, inst: Instruction, debug: bool) {
-            let input: isize = match self.inputbuffer.pop_front() {
-                Some(num) => {
-                    if debug {
-                        println!("input {}", num);
-                    };
-                    num
-                },
-                None => {
-                    if self.network_mode_enabled {
-                        -1
-                    } else {
-                        let mut input_string = String::new();
-                        println!("Please input a number.");
-                        io::stdin()
-                            .read_line(&mut input_string)
-                            .expect("error reading line");
-
-                        match input_string.trim().parse() {
-                            Ok(num) => num,
-                            Err(_) => return,
-                        }
-                    }
-                },
+        fn read(&mut self, inst: Instruction, debug: bool) -> Result<Option<Event>, Fault> {
+            let input = match self.inputbuffer.pop_front() {
+                Some(num) => num,
+                None if self.network_mode_enabled => -1,
+                None => return Ok(Some(Event::NeedInput)),
+            };
+            if debug {
+                println!("input {}", input);
             };
-            self.program[inst.target()] = input;
+            self.write_memory(inst.target(), input)?;
             self.ip += 2;
+            Ok(None)
         }
 
-        fn write(&mut self, inst: Instruction, debug: bool) -> isize {
-            let output = self.program[inst.first()];
+        fn write(&mut self, inst: Instruction, debug: bool) -> Result<isize, Fault> {
+            let output = self.read_param(0, inst.first())?;
             if debug {
                 println!("outputaddr {} = {}", inst.first(), output);
             };
-            //println!("{}", output);
             self.ip += 2;
-            output
+            Ok(output)
         }
 
-        fn jump_if_true(&mut self, inst: Instruction, debug: bool) {
+        fn jump_if(&mut self, inst: Instruction, when: bool, debug: bool) -> Result<(), Fault> {
+            let condition = self.read_param(0, inst.first())?;
             if debug {
-                println!("jump if [{}] containing [{}]", inst.first(), self.program[inst.first()]);
+                println!("jump if {} [{}] containing [{}]", when, inst.first(), condition);
             };
-            self.ip = if self.program[inst.first()] != 0 {
+            self.ip = if (condition != 0) == when {
+                let target = self.read_param(1, inst.second())?;
                 if debug {
-                    println!(" jump to {}", self.program[inst.second()]);
+                    println!(" jump to {}", target);
                 }
-                conv(self.program[inst.second()])
+                self.conv(target)?
             } else {
                 if debug {
                     println!(" jump to {}", self.ip + 3);
                 }
                 self.ip + 3
             };
+            Ok(())
         }
 
-        fn jump_if_false(&mut self, inst: Instruction, debug: bool) {
-            self.ip = if self.program[inst.first()] == 0 {
-                if debug {
-                    println!("jump if false {}", self.program[inst.second()]);
-                };
-                conv(self.program[inst.second()])
-            } else {
-                if debug {
-                    println!("not jump if false {}", self.ip + 3);
-                };
-                self.ip + 3
-            };
-        }
-
-        fn less_than(&mut self, inst: Instruction, debug: bool) {
-            self.program[inst.target()] =
-                if self.program[inst.first()] < self.program[inst.second()] {
-                    if debug {
-                        println!(
-                            "lt {} {} {}",
-                            self.program[inst.first()],
-                            self.program[inst.second()],
-                            self.program[inst.target()]
-                        );
-                    };
-                    1
-                } else {
-                    if debug {
-                        println!(
-                            "not lt {} {} {}",
-                            self.program[inst.first()],
-                            self.program[inst.second()],
-                            self.program[inst.target()]
-                        );
-                    };
-                    0
-                };
-            self.ip += 4;
-        }
-
-        fn equals(&mut self, inst: Instruction, debug: bool) {
+        fn compare(&mut self, inst: Instruction, holds: fn(&isize, &isize) -> bool, debug: bool) -> Result<(), Fault> {
+            let (a, b) = (self.read_param(0, inst.first())?, self.read_param(1, inst.second())?);
+            let result = if holds(&a, &b) { 1 } else { 0 };
             if debug {
-                println!(
-                    "[{}] := [{}] == [{}]",
-                    inst.target(),
-                    inst.first(),
-                    inst.second()
-                );
+                println!("[{}] := [{}] ? [{}]\n {} = {} ? {}", inst.target(), inst.first(), inst.second(), result, a, b);
             }
-            self.program[inst.target()] =
-                if self.program[inst.first()] == self.program[inst.second()] {
-                    if debug {
-                        println!(
-                            " {} == {}",
-                            self.program[inst.first()],
-                            self.program[inst.second()],
-                        );
-                    };
-                    1
-                } else {
-                    if debug {
-                        println!(
-                            " {} != {}",
-                            self.program[inst.first()],
-                            self.program[inst.second()],
-                        );
-                    };
-                    0
-                };
+            self.write_memory(inst.target(), result)?;
             self.ip += 4;
+            Ok(())
         }
 
-        fn adjust_rb(&mut self, inst: Instruction, debug: bool) {
-            let offset = self.program[inst.first()];
+        fn adjust_rb(&mut self, inst: Instruction, debug: bool) -> Result<(), Fault> {
+            let offset = self.read_param(0, inst.first())?;
+            let rb = self.checked(self.rb.checked_add(offset))?;
             if debug {
                 println!(
                     "adjust relative base from {} to {}",
                     self.rb,
-                    self.rb + offset
+                    rb
                 );
             };
-            self.rb += offset;
+            self.rb = rb;
             self.ip += 2;
+            Ok(())
         }
 
-        pub fn run_program(&mut self, debug: bool) -> Option<isize> {
-            let mut output: Option<isize> = None;
-            let size = self.program.len();
-            if self.ip >= size {
-                return output;
+        // decodes the parameters with the registered layout and leaves ip on a Halt
+        fn extension(&mut self, opcode: usize, debug: bool) -> Result<Effect, Fault> {
+            let opcode_def = self.registry.opcode(opcode).expect("unregistered extension").clone();
+            let mut args = Vec::with_capacity(opcode_def.params.len());
+            for (n, param) in opcode_def.params.iter().enumerate() {
+                let index = self.get_access_index(n, *param)?;
+                args.push(match param {
+                    Param::Write => index as isize,
+                    Param::Read | Param::Target => self.read_param(n, index)?,
+                });
             }
-            loop {
-                let inst = self.parse_instruction();
-                if debug {
-                    self.print_program();
-                    println!("[{}]:", self.ip);
-                };
-                match inst {
-                    Instruction::NOOP => panic!("NOOP"),
-                    Instruction::ADD(_, _, _) => self.add(inst, debug),
-                    Instruction::MULT(_, _, _) => self.mult(inst, debug),
-                    Instruction::READ(_) => self.read(inst, debug),
-                    Instruction::WRITE(_) => output = Some(self.write(inst, debug)),
-                    Instruction::JUMPIFTRUE(_, _) => self.jump_if_true(inst, debug),
-                    Instruction::JUMPIFFALSE(_, _) => self.jump_if_false(inst, debug),
-                    Instruction::LESSTHAN(_, _, _) => self.less_than(inst, debug),
-                    Instruction::EQUALS(_, _, _) => self.equals(inst, debug),
-                    Instruction::ADJUSTRB(_) => self.adjust_rb(inst, debug),
-                    Instruction::HALT => {
-                        if debug {
-                            println!("END")
-                        };
-                        break;
-                    }
+            if debug {
+                println!("{} {:?}", opcode_def.name, args);
+            }
+            let effect = (opcode_def.handler)(self, &args);
+            match effect {
+                Effect::Continue | Effect::Output(_) => self.ip += 1 + args.len(),
+                Effect::Jump(target) => self.ip = target,
+                Effect::Halt => (),
+            }
+            Ok(effect)
+        }
+
+        // Executes a single instruction. Halting and waiting for input leave ip where it is,
+        // so stepping again repeats the event. A watchpoint that pauses during an instruction
+        // with its own event is reported by the next step, which then executes nothing.
+        pub fn step(&mut self, debug: bool) -> Result<Option<Event>, Fault> {
+            if let Some(hit) = self.paused.take() {
+                return Ok(Some(Event::Watchpoint(hit)));
+            }
+            let event = self.execute(debug)?;
+            if event.is_none() {
+                if let Some(hit) = self.paused.take() {
+                    return Ok(Some(Event::Watchpoint(hit)));
                 }
             }
-            output
+            Ok(event)
         }
 
-        pub fn run_program_until_output(&mut self, debug: bool) -> Option<isize> {
-            let mut output: Option<isize> = None;
-            let size = self.program.len();
-            if self.ip >= size {
-                return output;
+        fn execute(&mut self, debug: bool) -> Result<Option<Event>, Fault> {
+            let inst = self.parse_instruction()?;
+            if debug {
+                self.print_program();
+                println!("[{}]:", self.ip);
+            };
+            match inst {
+                Instruction::ADD(_, _, _) => self.add(inst, debug)?,
+                Instruction::MULT(_, _, _) => self.mult(inst, debug)?,
+                Instruction::READ(_) => return self.read(inst, debug),
+                Instruction::WRITE(_) => return Ok(Some(Event::Output(self.write(inst, debug)?))),
+                Instruction::JUMPIFTRUE(_, _) => self.jump_if(inst, true, debug)?,
+                Instruction::JUMPIFFALSE(_, _) => self.jump_if(inst, false, debug)?,
+                Instruction::LESSTHAN(_, _, _) => self.compare(inst, isize::lt, debug)?,
+                Instruction::EQUALS(_, _, _) => self.compare(inst, isize::eq, debug)?,
+                Instruction::ADJUSTRB(_) => self.adjust_rb(inst, debug)?,
+                Instruction::EXTENSION(opcode) => match self.extension(opcode, debug)? {
+                    Effect::Output(value) => return Ok(Some(Event::Output(value))),
+                    Effect::Halt => return Ok(Some(Event::Halted)),
+                    Effect::Continue | Effect::Jump(_) => (),
+                },
+                Instruction::HALT => {
+                    if debug {
+                        println!("END")
+                    };
+                    return Ok(Some(Event::Halted));
+                }
+            }
+            Ok(None)
+        }
+
+        pub fn run_until_event(&mut self, debug: bool) -> Result<Event, Fault> {
+            if self.ip >= self.memory_size() && self.paused.is_none() {
+                return Ok(Event::Halted);
             }
             loop {
-                let inst = self.parse_instruction();
-                if debug {
-                    self.print_program()
-                };
-                print!("");
-                match inst {
-                    Instruction::NOOP => continue,
-                    Instruction::ADD(_, _, _) => self.add(inst, debug),
-                    Instruction::MULT(_, _, _) => self.mult(inst, debug),
-                    Instruction::READ(_) => self.read(inst, debug),
-                    Instruction::WRITE(_) => {output = Some(self.write(inst, debug)); break;},
-                    Instruction::JUMPIFTRUE(_, _) => self.jump_if_true(inst, debug),
-                    Instruction::JUMPIFFALSE(_, _) => self.jump_if_false(inst, debug),
-                    Instruction::LESSTHAN(_, _, _) => self.less_than(inst, debug),
-                    Instruction::EQUALS(_, _, _) => self.equals(inst, debug),
-                    Instruction::ADJUSTRB(_) => self.adjust_rb(inst, debug),
-                    Instruction::HALT => {
-                        if debug {
-                            println!("END")
-                        };
-                        break;
-                    }
+                if let Some(event) = self.step(debug)? {
+                    return Ok(event);
                 }
             }
-            output
         }
 
-        pub fn run_program_in_compatibility_mode(&mut self, noun: isize, verb: isize, debug: bool) -> isize {
-            self.program[1] = noun;
-            self.program[2] = verb;
+        // The run modes below panic on faults, ask on stdin when the input buffer runs dry
+        // and do not pause on watchpoints, their callbacks still run.
+        fn next_event(&mut self, debug: bool) -> Event {
             loop {
-                let inst = self.parse_instruction();
-                if debug {
-                    self.print_program()
-                };
-                print!("");
-                match inst {
-                    Instruction::NOOP => continue,
-                    Instruction::ADD(_, _, _) => self.add(inst, debug),
-                    Instruction::MULT(_, _, _) => self.mult(inst, debug),
-                    Instruction::HALT => {
-                        if debug {
-                            println!("END")
-                        };
-                        break;
-                    }
-                    _ => panic!("instruction not supported in compatibility mode")
+                match self.run_until_event(debug) {
+                    Ok(Event::NeedInput) => self.prompt_input(),
+                    Ok(Event::Watchpoint(_)) => (),
+                    Ok(event) => return event,
+                    Err(fault) => panic!("{}", fault),
                 }
             }
-            self.program[0]
         }
-        
+
+        fn prompt_input(&mut self) {
+            let mut input_string = String::new();
+            println!("Please input a number.");
+            io::stdin()
+                .read_line(&mut input_string)
+                .expect("error reading line");
+            if let Ok(num) = input_string.trim().parse() {
+                self.push_input(num);
+            }
+        }
+
+        pub fn run_program(&mut self, debug: bool) -> Option<isize> {
+            let mut output: Option<isize> = None;
+            while let Event::Output(value) = self.next_event(debug) {
+                output = Some(value);
+            }
+            output
+        }
+
+        pub fn run_program_until_output(&mut self, debug: bool) -> Option<isize> {
+            match self.next_event(debug) {
+                Event::Output(value) => Some(value),
+                _ => None,
+            }
+        }
+
+        pub fn run_program_in_compatibility_mode(&mut self, noun: isize, verb: isize, debug: bool) -> isize {
+            let inputs = Patch::new("noun-verb").set(1, self.program[1], noun).set(2, self.program[2], verb);
+            self.apply_patch(&inputs).unwrap_or_else(|e| panic!("{}", e));
+            let profile = self.profile;
+            self.profile = Profile::Day2;
+            let event = self.next_event(debug);
+            self.profile = profile;
+            if event != Event::Halted {
+                panic!("instruction not supported in compatibility mode");
+            }
+            self.load(0)
+        }
+
         pub fn test_run(&mut self, debug: bool) {
-                let inst = self.parse_instruction();
-                if debug {
-                    self.print_program()
-                };
-                match inst {
-                    Instruction::NOOP => (),
-                    Instruction::ADD(_, _, _) => self.add(inst, debug),
-                    Instruction::MULT(_, _, _) => self.mult(inst, debug),
-                    Instruction::READ(_) => self.read(inst, debug),
-                    Instruction::WRITE(_) => {self.write(inst, debug);()},
-                    Instruction::JUMPIFTRUE(_, _) => self.jump_if_true(inst, debug),
-                    Instruction::JUMPIFFALSE(_, _) => self.jump_if_false(inst, debug),
-                    Instruction::LESSTHAN(_, _, _) => self.less_than(inst, debug),
-                    Instruction::EQUALS(_, _, _) => self.equals(inst, debug),
-                    Instruction::ADJUSTRB(_) => self.adjust_rb(inst, debug),
-                    Instruction::HALT => {
-                        if debug {
-                            println!("END")
-                        };
-                        ()
-                    },
+            loop {
+                match self.step(debug) {
+                    Ok(Some(Event::NeedInput)) => self.prompt_input(),
+                    Ok(_) => break,
+                    Err(fault) => panic!("{}", fault),
                 }
+            }
         }
+
         pub fn get_program_clone(&self) -> Vec<isize> {
-            self.program.clone()
+            let mut program = self.program.to_vec();
+            program.resize(self.memory_size(), 0);
+            program
+        }
+
+        // The words allocated so far, bypassing mapped devices. Until the first write a program
+        // from from_static has no padding yet, so this can be shorter than memory_size; cell
+        // sees all of memory and memory_mut allocates the rest.
+        pub fn memory(&self) -> &[isize] {
+            &self.program
+        }
+
+        pub fn memory_mut(&mut self) -> &mut [isize] {
+            self.materialize()
+        }
+
+        pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), patch::Error> {
+            patch.apply(self.materialize())
+        }
+
+        pub fn revert_patch(&mut self, patch: &Patch) -> Result<(), patch::Error> {
+            patch.revert(self.materialize())
+        }
+
+        pub fn ip(&self) -> usize {
+            self.ip
+        }
+
+        pub fn set_ip(&mut self, ip: usize) {
+            self.ip = ip;
+        }
+
+        pub fn relative_base(&self) -> isize {
+            self.rb
+        }
+
+        pub fn set_relative_base(&mut self, rb: isize) {
+            self.rb = rb;
+        }
+
+        pub fn pending_input(&self) -> usize {
+            self.inputbuffer.len()
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::zero_prefixed_literal)]
 mod tests {
     #[test]
     fn compatibility() {
@@ -542,30 +788,6 @@ mod tests {
           let debug = false;
           assert_eq!(3516593, computer.run_program_in_compatibility_mode(12, 2, debug));
     }
-    #[test]
-    fn day5_part1() {
-        let program = crate::intcode::read_data("5");
-        let mut computer = crate::intcode::Amplifier::new(program.clone(), vec![]);
-        computer.push_input(1);
-        assert_eq!(15508323, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_add_positional() {
-        let program = vec![1,2,3,0,4,0,99];
-        let mut computer = crate::intcode::Amplifier::new_test(program, vec![]);
-        computer.test_run(false);
-        assert_eq!(vec![3,2,3,0,4,0,99], computer.get_program_clone());
-    }
-
-
-    #[test]
-    fn test_add_immediate() {
-        let program = vec![01101,2,3,0,4,0,99];
-        let mut computer = crate::intcode::Amplifier::new_test(program, vec![]);
-        computer.test_run(false);
-        assert_eq!(vec![5,2,3,0,4,0,99], computer.get_program_clone());
-    }
 
     #[test]
     fn test_get_modes() {
@@ -586,137 +808,67 @@ mod tests {
     }
 
     #[test]
-    fn test_add_relative() {
-        let program = vec![01109,2,02201,2,6,0,4,0,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        assert_eq!(105, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_jump_if_true_false() {
-        let program = vec![01105,0,6,00004,1,99,00004,2,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        assert_eq!(0, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_jump_if_true_true() {
-        let program = vec![01105,1,6,00004,1,99,00004,2,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        assert_eq!(6, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_jump_if_true_immediate_false() {
-        let program = vec![01105,0,6,00104,1,99,00104,2,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        assert_eq!(1, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_jump_if_false_false() {
-        let program = vec![01106,0,6,00004,1,99,00004,2,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        assert_eq!(6, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_jump_if_false_true() {
-        let program = vec![01106,1,6,00004,1,99,00004,2,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        assert_eq!(1, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_jump_if_false_immediate_false() {
-        let program = vec![01106,0,6,00104,1,99,00104,2,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        assert_eq!(2, computer.run_program(false).unwrap());
+    fn static_programs() {
+        static PROGRAM: [i64; 9] = [1101, 2, 3, 8, 4, 8, 99, 0, 0];
+        let mut computer = crate::intcode::Amplifier::from_static(&PROGRAM, vec![]);
+        let snapshot = computer.clone();
+        assert_eq!(PROGRAM.as_ptr() as *const isize, computer.memory().as_ptr());
+        assert_eq!((9 + 40960, Some(0), None), (computer.memory_size(), computer.cell(100), computer.cell(9 + 40960)));
+        assert_eq!(Some(5), computer.run_program(false));
+        assert_eq!(9 + 40960, computer.memory().len());
+        assert_eq!(PROGRAM.as_ptr() as *const isize, snapshot.memory().as_ptr());
+        assert_eq!(0, PROGRAM[8]);
     }
 
     #[test]
-    fn test_jump_if_false_immediate_true() {
-        let program = vec![01106,1,6,00104,1,99,00104,2,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        assert_eq!(1, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_small_5_equal_position_false() {
-        let program = vec![3,9,8,9,10,9,4,9,99,-1,8];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        computer.push_input(1);
-        assert_eq!(0, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_small_5_equal_position_true() {
-        let program = vec![3,9,8,9,10,9,4,9,99,-1,8];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        computer.push_input(8);
-        assert_eq!(1, computer.run_program(false).unwrap());
-    }
-
-    #[test]
-    fn test_small_5_less_than_position_false() {
-        let program = vec![3,9,7,9,10,9,4,9,99,-1,8];
-        let mut computer = crate::intcode::Amplifier::new_test(program, vec![]);
-        computer.push_input(8);
-        let result = computer.run_program(false).unwrap();
-        println!("{:?}", computer.get_program_clone());
-        assert_eq!(0, result);
-    }
-
-    #[test]
-    fn test_small_5_less_than_position_true() {
-        let program = vec![3,9,7,9,10,9,4,9,99,-1,8];
-        let mut computer = crate::intcode::Amplifier::new_test(program, vec![]);
-        computer.push_input(1);
-        let result = computer.run_program(false).unwrap();
-        println!("{:?}", computer.get_program_clone());
-        assert_eq!(1, result);
-    }
-
-    #[test]
-    fn test_small_5_equal_immediate_false() {
-        let program = vec![3,3,1108,-1,8,3,4,3,99];
+    fn run_until_event() {
+        use crate::intcode::Event;
+        let program = vec![3,9,1002,9,2,9,4,9,99,0];
         let mut computer = crate::intcode::Amplifier::new_test(program, vec![]);
-        computer.push_input(1);
-        assert_eq!(0, computer.run_program(false).unwrap());
+        assert_eq!(Ok(Event::NeedInput), computer.run_until_event(false));
+        assert_eq!(Ok(Event::NeedInput), computer.run_until_event(false));
+        computer.push_input(21);
+        assert_eq!(Ok(Event::Output(42)), computer.run_until_event(false));
+        assert_eq!(Ok(Event::Halted), computer.run_until_event(false));
+        assert_eq!(Ok(Event::Halted), computer.run_until_event(false));
     }
 
     #[test]
-    fn test_small_5_equal_immediate_true() {
-        let program = vec![3,3,1108,-1,8,3,4,3,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        computer.push_input(8);
-        assert_eq!(1, computer.run_program(false).unwrap());
-    }
+    fn profiles() {
+        use crate::intcode::{Fault, Profile};
+        let mut computer = crate::intcode::Amplifier::new(crate::intcode::read_data("5"), vec![1]);
+        computer.set_profile(Profile::Day2);
+        assert_eq!(Err(Fault::NotInProfile { address: 0, opcode: 3, profile: Profile::Day2 }),
+                   computer.run_until_event(false));
+        computer.set_profile(Profile::Day5);
+        assert_eq!(15508323, computer.run_program(false).unwrap());
 
-    #[test]
-    fn test_small_5_less_than_immediate_false() {
-        let program = vec![3,3,1107,-1,8,3,4,3,99];
-        let mut computer = crate::intcode::Amplifier::new_test(program, vec![]);
-        computer.push_input(8);
-        let result = computer.run_program(false).unwrap();
-        println!("{:?}", computer.get_program_clone());
-        assert_eq!(0, result);
+        let mut computer = crate::intcode::Amplifier::new(crate::intcode::read_data("9"), vec![1]);
+        computer.set_profile(Profile::Day5);
+        assert!(matches!(computer.run_until_event(false), Err(Fault::NotInProfile { opcode: 9, .. })));
     }
 
     #[test]
-    fn test_small_5_less_than_immediate_true() {
-        let program = vec![3,3,1107,-1,8,3,4,3,99];
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        computer.push_input(1);
-        assert_eq!(1, computer.run_program(false).unwrap());
+    fn faults() {
+        use crate::intcode::{Amplifier, Fault};
+        let run = |program: Vec<isize>| Amplifier::new_test(program, vec![]).run_until_event(false);
+        assert_eq!(Err(Fault::IllegalOpcode { address: 0, opcode: 12 }), run(vec![12, 0, 0, 0]));
+        assert_eq!(Err(Fault::ImmediateWrite { address: 0 }), run(vec![11101, 0, 0, 0]));
+        assert_eq!(Err(Fault::InvalidMode { address: 0, mode: 3 }), run(vec![304, 0]));
+        assert_eq!(Err(Fault::NegativeAddress { address: 0, value: -1 }), run(vec![4, -1]));
+        assert_eq!(Err(Fault::OutOfBounds { address: 0, index: 7 }), run(vec![4, 7, 99]));
+        assert_eq!(Err(Fault::NegativeAddress { address: 0, value: -5 }), run(vec![1105, 1, -5]));
+        assert_eq!(Err(Fault::Overflow { address: 2 }), run(vec![109, isize::MAX, 204, 1, 99]));
+        assert_eq!(Err(Fault::Overflow { address: 2 }), run(vec![109, isize::MAX, 109, 1, 99]));
+        assert_eq!(Err(Fault::Overflow { address: 0 }), run(vec![1101, isize::MAX, 1, 0, 99]));
+        assert_eq!(Err(Fault::Overflow { address: 0 }), run(vec![1102, isize::MIN, -1, 0, 99]));
     }
 
     #[test]
-    fn day5_part2() {
+    #[should_panic(expected = "not part of the Day2 instruction set")]
+    fn compatibility_mode_rejects_io() {
         let program = crate::intcode::read_data("5");
-        let mut computer = crate::intcode::Amplifier::new(program.clone(), vec![]);
-        computer.push_input(5);
-        assert_eq!(9006327, computer.run_program(false).unwrap());
+        crate::intcode::Amplifier::new(program, vec![]).run_program_in_compatibility_mode(0, 0, false);
     }
 
     #[test]
@@ -740,39 +892,4 @@ mod tests {
         let result = amp_e.run_program(false).unwrap();
         assert_eq!(11828, result);
     }
-
-    #[test]
-    fn day9_part1() {
-        let program = crate::intcode::read_data("9");
-        let mut computer = crate::intcode::Amplifier::new(program, vec![1]);
-        let result = computer.run_program(false).unwrap();
-        assert_eq!(3497884671, result);
-    }
-
-    #[test]
-    fn day9_part2() {
-        let program = crate::intcode::read_data("9");
-        let mut computer = crate::intcode::Amplifier::new(program, vec![2]);
-        let result = computer.run_program(false).unwrap();
-        assert_eq!(46470, result);
-    }
-
-    #[test]
-    fn day_13_part1() {
-        let program = crate::intcode::read_data("13");
-        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
-        let result = computer.run_program_until_output(false).unwrap();
-        let result1 = computer.run_program_until_output(false).unwrap();
-        let result2 = computer.run_program_until_output(false).unwrap();
-        print!("{}, {}, {}", result, result1, result2);
-    }
-
-    
-    #[test]
-    fn day23_part1() {
-        let program = crate::intcode::read_data("23");
-        let mut computer = crate::intcode::Amplifier::new(program,vec![-1]);
-        let debug = true;
-        assert_eq!(46470, computer.run_program(debug).unwrap());
-    }
 }
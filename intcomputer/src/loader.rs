@@ -0,0 +1,239 @@
+use crate::image::{self, Image};
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    // line and column are 1-based and point at the start of the token
+    Parse { line: usize, column: usize, token: String },
+    EmptyValue { line: usize, column: usize },
+    Empty,
+    Checksum { expected: u64, actual: u64 },
+    // binary images
+    UnsupportedVersion(u8),
+    UnsupportedWidth(u8),
+    Truncated,
+    Overlong,
+    Trailing(usize),
+    MemoryHint(usize),
+    Entry(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse { line, column, token } => write!(f, "{}:{}: invalid value {:?}", line, column, token),
+            Error::EmptyValue { line, column } => write!(f, "{}:{}: missing value between commas", line, column),
+            Error::Empty => write!(f, "program is empty"),
+            Error::Checksum { expected, actual } =>
+                write!(f, "checksum mismatch: expected {:016x}, got {:016x}", expected, actual),
+            Error::UnsupportedVersion(version) => write!(f, "unsupported image version {}", version),
+            Error::UnsupportedWidth(width) => write!(f, "unsupported word width of {} bits", width),
+            Error::Truncated => write!(f, "image is truncated"),
+            Error::Overlong => write!(f, "value in image does not fit into a word"),
+            Error::Trailing(count) => write!(f, "{} bytes after the end of the image", count),
+            Error::MemoryHint(hint) =>
+                write!(f, "memory hint of {} words is below the program size or above {}", hint, image::MAX_MEMORY),
+            Error::Entry(entry) => write!(f, "entry point {} is outside of memory", entry),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+// FNV-1a over the little endian 64 bit words of the image
+pub fn checksum(program: &[isize]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for value in program.iter() {
+        for byte in (*value as i64).to_le_bytes().iter() {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Loader {
+    // `#` starts a comment that runs to the end of the line
+    pub allow_comments: bool,
+    pub checksum: Option<u64>,
+}
+
+impl Loader {
+    pub fn new() -> Loader {
+        Loader::default()
+    }
+
+    pub fn load_str(&self, source: &str) -> Result<Vec<isize>, Error> {
+        let mut program = Vec::new();
+        let mut chars = Vec::new();
+        let (mut line, mut column) = (1, 1);
+        for c in source.chars() {
+            chars.push((line, column, c));
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        // a value is expected at the start and after every comma
+        let mut expect_value = true;
+        let mut i = 0;
+        while i < chars.len() {
+            let (line, column, c) = chars[i];
+            if c == '#' && self.allow_comments {
+                while i < chars.len() && chars[i].2 != '\n' {
+                    i += 1;
+                }
+            } else if c == ',' {
+                if expect_value {
+                    return Err(Error::EmptyValue { line, column });
+                }
+                expect_value = true;
+                i += 1;
+            } else if c.is_whitespace() {
+                i += 1;
+            } else {
+                let mut token = String::new();
+                while i < chars.len() {
+                    let c = chars[i].2;
+                    if c == ',' || c.is_whitespace() || (c == '#' && self.allow_comments) {
+                        break;
+                    }
+                    token.push(c);
+                    i += 1;
+                }
+                match token.parse::<isize>() {
+                    // two values without a comma in between are a typo as well
+                    Ok(value) if expect_value => program.push(value),
+                    _ => return Err(Error::Parse { line, column, token }),
+                }
+                expect_value = false;
+            }
+        }
+        // a trailing comma is fine, some inputs end with one
+
+        if program.is_empty() {
+            return Err(Error::Empty);
+        }
+        self.verify(&program)?;
+        Ok(program)
+    }
+
+    // text or binary image, told apart by the image magic
+    pub fn load_bytes(&self, bytes: &[u8]) -> Result<Vec<isize>, Error> {
+        self.load_image(bytes).map(|image| image.program)
+    }
+
+    // like load_bytes, but keeps the entry point and memory hint of binary images
+    pub fn load_image(&self, bytes: &[u8]) -> Result<Image, Error> {
+        if image::is_image(bytes) {
+            let image = image::decode(bytes)?;
+            self.verify(&image.program)?;
+            return Ok(image);
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(source) => self.load_str(source).map(Image::new),
+            Err(e) => Err(Error::Io(io::Error::new(io::ErrorKind::InvalidData, e))),
+        }
+    }
+
+    pub fn load_reader<R: Read>(&self, mut reader: R) -> Result<Vec<isize>, Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        self.load_bytes(&bytes)
+    }
+
+    pub fn load_path<P: AsRef<Path>>(&self, path: P) -> Result<Vec<isize>, Error> {
+        self.load_bytes(&fs::read(path)?)
+    }
+
+    pub fn load_stdin(&self) -> Result<Vec<isize>, Error> {
+        self.load_reader(io::stdin().lock())
+    }
+
+    fn verify(&self, program: &[isize]) -> Result<(), Error> {
+        if let Some(expected) = self.checksum {
+            let actual = checksum(program);
+            if actual != expected {
+                return Err(Error::Checksum { expected, actual });
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<isize>, Error> {
+    Loader::new().load_path(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loader::{checksum, load, Error, Loader};
+
+    #[test]
+    fn whitespace_and_newlines() {
+        assert_eq!(vec![1, -2, 3, 99], Loader::new().load_str(" 1,\n-2 ,3,\r\n 99\n").unwrap());
+        assert_eq!(vec![1, 0, 99], Loader::new().load_str("1,0,99,\n").unwrap());
+    }
+
+    #[test]
+    fn typo_is_reported_with_position() {
+        match Loader::new().load_str("1,2,3,\n4,5x,6") {
+            Err(Error::Parse { line, column, token }) => {
+                assert_eq!((2, 3), (line, column));
+                assert_eq!("5x", token);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_values() {
+        match Loader::new().load_str("1,,2") {
+            Err(Error::EmptyValue { line: 1, column: 3 }) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+        match Loader::new().load_str("1,2 3") {
+            Err(Error::Parse { line: 1, column: 5, .. }) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(Loader::new().load_str(" \n"), Err(Error::Empty)));
+    }
+
+    #[test]
+    fn comments() {
+        let source = "# add\n1,0,0,0, # 4 words\n99";
+        assert!(Loader::new().load_str(source).is_err());
+        let loader = Loader { allow_comments: true, ..Loader::new() };
+        assert_eq!(vec![1, 0, 0, 0, 99], loader.load_str(source).unwrap());
+    }
+
+    #[test]
+    fn checksum_is_verified() {
+        let program = vec![1, 0, 0, 0, 99];
+        let loader = Loader { checksum: Some(checksum(&program)), ..Loader::new() };
+        assert_eq!(program, loader.load_reader("1,0,0,0,99".as_bytes()).unwrap());
+        assert!(matches!(loader.load_str("1,0,0,1,99"), Err(Error::Checksum { .. })));
+    }
+
+    #[test]
+    fn missing_file() {
+        assert!(matches!(load("does-not-exist"), Err(Error::Io(_))));
+        assert_eq!(crate::intcode::read_data("9"), load("9").unwrap());
+    }
+}
@@ -0,0 +1,233 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// Patch files list the changes of one or more named patches:
+//
+//   # comments run to the end of the line
+//   [free-play]
+//   0: 1 -> 2
+//
+// Every change is `address: original -> new`, a patch only applies if all originals match.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Change {
+    pub address: usize,
+    pub original: isize,
+    pub new: isize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    pub name: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Mismatch { patch: String, address: usize, expected: isize, actual: isize },
+    OutOfBounds { patch: String, address: usize },
+    // line is 1-based
+    Parse { line: usize, text: String },
+    Unknown(String),
+    Io(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Mismatch { patch, address, expected, actual } =>
+                write!(f, "patch {}: expected {} at {}, found {}", patch, expected, address, actual),
+            Error::OutOfBounds { patch, address } => write!(f, "patch {}: address {} out of bounds", patch, address),
+            Error::Parse { line, text } => write!(f, "{}: invalid patch line {:?}", line, text),
+            Error::Unknown(name) => write!(f, "no patch named {:?}", name),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Patch {
+    pub fn new(name: &str) -> Patch {
+        Patch { name: name.to_string(), changes: Vec::new() }
+    }
+
+    pub fn set(mut self, address: usize, original: isize, new: isize) -> Patch {
+        self.changes.push(Change { address, original, new });
+        self
+    }
+
+    // the patch that undoes this one
+    pub fn reversed(&self) -> Patch {
+        let mut changes: Vec<Change> =
+            self.changes.iter().map(|c| Change { address: c.address, original: c.new, new: c.original }).collect();
+        changes.reverse();
+        Patch { name: self.name.clone(), changes }
+    }
+
+    fn verify(&self, memory: &[isize]) -> Result<(), Error> {
+        // later changes of the same address expect the value of the earlier ones
+        let mut patched: Vec<(usize, isize)> = Vec::new();
+        for change in self.changes.iter() {
+            let actual = match patched.iter().rev().find(|(address, _)| *address == change.address) {
+                Some((_, value)) => *value,
+                None => *memory
+                    .get(change.address)
+                    .ok_or_else(|| Error::OutOfBounds { patch: self.name.clone(), address: change.address })?,
+            };
+            if actual != change.original {
+                return Err(Error::Mismatch {
+                    patch: self.name.clone(),
+                    address: change.address,
+                    expected: change.original,
+                    actual,
+                });
+            }
+            patched.push((change.address, change.new));
+        }
+        Ok(())
+    }
+
+    // leaves memory untouched if any original value does not match
+    pub fn apply(&self, memory: &mut [isize]) -> Result<(), Error> {
+        self.verify(memory)?;
+        for change in self.changes.iter() {
+            memory[change.address] = change.new;
+        }
+        Ok(())
+    }
+
+    pub fn revert(&self, memory: &mut [isize]) -> Result<(), Error> {
+        self.reversed().apply(memory)
+    }
+
+    pub fn is_applied(&self, memory: &[isize]) -> bool {
+        self.reversed().verify(memory).is_ok()
+    }
+}
+
+pub fn parse_change(text: &str) -> Option<Change> {
+    let colon = text.find(':')?;
+    let arrow = text.find("->")?;
+    if arrow < colon {
+        return None;
+    }
+    Some(Change {
+        address: text[..colon].trim().parse().ok()?,
+        original: text[colon + 1..arrow].trim().parse().ok()?,
+        new: text[arrow + 2..].trim().parse().ok()?,
+    })
+}
+
+pub fn parse(source: &str) -> Result<Vec<Patch>, Error> {
+    let mut patches: Vec<Patch> = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let text = match line.find('#') {
+            Some(i) => &line[..i],
+            None => line,
+        }
+        .trim();
+        let error = || Error::Parse { line: number + 1, text: line.to_string() };
+        if text.is_empty() {
+            continue;
+        }
+        if text.starts_with('[') && text.ends_with(']') && text.len() > 2 {
+            patches.push(Patch::new(text[1..text.len() - 1].trim()));
+            continue;
+        }
+        let change = parse_change(text).ok_or_else(error)?;
+        patches.last_mut().ok_or_else(error)?.changes.push(change);
+    }
+    Ok(patches)
+}
+
+pub fn to_text(patches: &[Patch]) -> String {
+    let mut text = String::new();
+    for patch in patches.iter() {
+        text.push_str(&format!("[{}]\n", patch.name));
+        for change in patch.changes.iter() {
+            text.push_str(&format!("{}: {} -> {}\n", change.address, change.original, change.new));
+        }
+    }
+    text
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Patch>, Error> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))?;
+    parse(&source)
+}
+
+// patches for the puzzle programs
+pub fn named(name: &str) -> Result<Patch, Error> {
+    match name {
+        // day 13, play without inserting quarters
+        "free-play" => Ok(Patch::new(name).set(0, 1, 2)),
+        // day 17, wake the vacuum robot up
+        "wake-up" => Ok(Patch::new(name).set(0, 1, 2)),
+        _ => Err(Error::Unknown(name.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intcode::read_data;
+    use crate::patch::{self, Error, Patch};
+
+    #[test]
+    fn apply_and_revert() {
+        let mut memory = vec![1, 0, 0, 3, 99];
+        let patch = Patch::new("1202").set(1, 0, 12).set(2, 0, 2);
+        patch.apply(&mut memory).unwrap();
+        assert_eq!(vec![1, 12, 2, 3, 99], memory);
+        assert!(patch.is_applied(&memory));
+        assert_eq!(
+            Err(Error::Mismatch { patch: "1202".to_string(), address: 1, expected: 0, actual: 12 }),
+            patch.apply(&mut memory)
+        );
+        patch.revert(&mut memory).unwrap();
+        assert_eq!(vec![1, 0, 0, 3, 99], memory);
+
+        // nothing is written when a later change does not match
+        let broken = Patch::new("broken").set(0, 1, 2).set(9, 0, 1);
+        assert_eq!(Err(Error::OutOfBounds { patch: "broken".to_string(), address: 9 }), broken.apply(&mut memory));
+        assert_eq!(1, memory[0]);
+    }
+
+    #[test]
+    fn files() {
+        let source = "
+            # two patches
+            [free-play]
+            0: 1 -> 2
+            [twice]   # the same address changes two times
+            5: 0 -> 1
+            5: 1 -> -7
+        ";
+        let patches = patch::parse(source).unwrap();
+        assert_eq!(patch::named("free-play").unwrap(), patches[0]);
+        assert_eq!(Patch::new("twice").set(5, 0, 1).set(5, 1, -7), patches[1]);
+        assert_eq!(patches, patch::parse(&patch::to_text(&patches)).unwrap());
+
+        let mut memory = vec![1, 0, 0, 0, 0, 0];
+        patches[1].apply(&mut memory).unwrap();
+        assert_eq!(-7, memory[5]);
+        patches[1].revert(&mut memory).unwrap();
+        assert_eq!(0, memory[5]);
+
+        assert_eq!(Err(Error::Parse { line: 1, text: "0: 1 -> 2".to_string() }), patch::parse("0: 1 -> 2"));
+        assert!(patch::parse("[x]\n0 1 2").is_err());
+        assert_eq!(Err(Error::Unknown("cheat".to_string())), patch::named("cheat"));
+    }
+
+    #[test]
+    fn puzzle_programs() {
+        let mut day13 = read_data("../13Dec/program");
+        patch::named("free-play").unwrap().apply(&mut day13).unwrap();
+        assert_eq!(2, day13[0]);
+        let mut day17 = read_data("../17Dec/program");
+        patch::named("wake-up").unwrap().apply(&mut day17).unwrap();
+        assert!(patch::named("free-play").unwrap().apply(&mut day17).is_err());
+    }
+}
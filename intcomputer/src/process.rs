@@ -0,0 +1,276 @@
+use crate::intcode::{Amplifier, Event, Fault};
+use std::convert::TryFrom;
+
+pub type Pid = usize;
+
+// How the outputs of a process are cut into messages.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Framing {
+    // every output is a message of its own, to the given process or to the host
+    Stream(Option<Pid>),
+    // a destination pid followed by a fixed number of words, day 23 packets use 2
+    Addressed(usize),
+}
+
+// None stands for the host on either end
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub from: Option<Pid>,
+    pub to: Option<Pid>,
+    pub words: Vec<isize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Exit {
+    Halted,
+    Killed,
+    Faulted(Fault),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Ready,
+    // waiting on an empty input buffer, a message makes it ready again
+    Blocked,
+    Exited(Exit),
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Usage {
+    pub instructions: u64,
+    pub slices: u64,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Round {
+    pub instructions: u64,
+    pub messages: usize,
+}
+
+#[derive(Debug)]
+struct Process {
+    amp: Amplifier,
+    framing: Framing,
+    partial: Vec<isize>,
+    status: Status,
+    usage: Usage,
+}
+
+// Round robin scheduler, every ready process runs for at most `quantum` instructions per round.
+// Messages to exited or unknown processes end up in the outbox of the host.
+#[derive(Debug)]
+pub struct Manager {
+    quantum: usize,
+    processes: Vec<Process>,
+    outbox: Vec<Message>,
+}
+
+impl Manager {
+    pub fn new(quantum: usize) -> Manager {
+        assert!(quantum > 0, "quantum must be at least one instruction");
+        Manager { quantum, processes: Vec::new(), outbox: Vec::new() }
+    }
+
+    // pids are handed out in order, starting at 0
+    pub fn spawn(&mut self, amp: Amplifier, framing: Framing) -> Pid {
+        self.processes.push(Process {
+            amp,
+            framing,
+            partial: Vec::new(),
+            status: Status::Ready,
+            usage: Usage::default(),
+        });
+        self.processes.len() - 1
+    }
+
+    pub fn kill(&mut self, pid: Pid) -> bool {
+        match self.processes.get_mut(pid) {
+            Some(process) if !matches!(process.status, Status::Exited(_)) => {
+                process.status = Status::Exited(Exit::Killed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn send(&mut self, to: Pid, words: &[isize]) {
+        self.deliver(Message { from: None, to: Some(to), words: words.to_vec() });
+    }
+
+    pub fn status(&self, pid: Pid) -> Option<&Status> {
+        self.processes.get(pid).map(|process| &process.status)
+    }
+
+    pub fn usage(&self, pid: Pid) -> Option<Usage> {
+        self.processes.get(pid).map(|process| process.usage)
+    }
+
+    pub fn amplifier(&self, pid: Pid) -> Option<&Amplifier> {
+        self.processes.get(pid).map(|process| &process.amp)
+    }
+
+    pub fn take_outbox(&mut self) -> Vec<Message> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.processes.iter().all(|process| matches!(process.status, Status::Exited(_)))
+    }
+
+    fn deliver(&mut self, message: Message) {
+        match message.to.and_then(|pid| self.processes.get_mut(pid)) {
+            Some(process) if !matches!(process.status, Status::Exited(_)) => {
+                process.amp.push_input_vec(message.words);
+                if process.status == Status::Blocked {
+                    process.status = Status::Ready;
+                }
+            }
+            _ => self.outbox.push(message),
+        }
+    }
+
+    fn slice(&mut self, pid: Pid, messages: &mut Vec<Message>) -> u64 {
+        let quantum = self.quantum;
+        let process = &mut self.processes[pid];
+        process.usage.slices += 1;
+        let mut executed = 0;
+        while executed < quantum {
+            let event = match process.amp.step(false) {
+                Ok(Some(Event::NeedInput)) => {
+                    process.status = Status::Blocked;
+                    break;
+                }
+                Ok(event) => event,
+                Err(fault) => {
+                    process.status = Status::Exited(Exit::Faulted(fault));
+                    break;
+                }
+            };
+            executed += 1;
+            match event {
+                Some(Event::Output(value)) => match process.framing {
+                    Framing::Stream(to) => messages.push(Message { from: Some(pid), to, words: vec![value] }),
+                    Framing::Addressed(words) => {
+                        process.partial.push(value);
+                        if process.partial.len() == words + 1 {
+                            let to = usize::try_from(process.partial[0]).ok();
+                            let words = process.partial.split_off(1);
+                            process.partial.clear();
+                            messages.push(Message { from: Some(pid), to, words });
+                        }
+                    }
+                },
+                Some(Event::Halted) => {
+                    process.status = Status::Exited(Exit::Halted);
+                    break;
+                }
+                _ => (),
+            }
+        }
+        process.usage.instructions += executed as u64;
+        executed as u64
+    }
+
+    // messages are delivered at the end of the sender's slice
+    pub fn run_round(&mut self) -> Round {
+        let mut round = Round::default();
+        for pid in 0..self.processes.len() {
+            if self.processes[pid].status != Status::Ready {
+                continue;
+            }
+            let mut messages = Vec::new();
+            round.instructions += self.slice(pid, &mut messages);
+            round.messages += messages.len();
+            for message in messages {
+                self.deliver(message);
+            }
+        }
+        round
+    }
+
+    // Runs until no process is ready. Processes in network mode never block, drive those with run_round.
+    pub fn run(&mut self) {
+        while self.processes.iter().any(|process| process.status == Status::Ready) {
+            self.run_round();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intcode::{self, Amplifier, Fault};
+    use crate::process::{Exit, Framing, Manager, Message, Status, Usage};
+
+    #[test]
+    fn amplifier_chain() {
+        // day 7 part 1, each amplifier streams into the next one and the last one to the host
+        let program = intcode::read_data("7");
+        let mut manager = Manager::new(10);
+        for (i, phase) in [4, 0, 2, 3, 1].iter().enumerate() {
+            let to = if i < 4 { Some(i + 1) } else { None };
+            manager.spawn(Amplifier::new(program.clone(), vec![*phase]), Framing::Stream(to));
+        }
+        manager.send(0, &[0]);
+        manager.run();
+        assert!(manager.is_finished());
+        assert_eq!(vec![Message { from: Some(4), to: None, words: vec![11828] }], manager.take_outbox());
+        assert_eq!(Some(&Status::Exited(Exit::Halted)), manager.status(2));
+    }
+
+    #[test]
+    fn addressed_messages() {
+        // reads a value and sends (value, value * 2) to pid 1, which sends their sum to 7
+        let doubler = vec![3, 100, 104, 1, 4, 100, 1002, 100, 2, 100, 4, 100, 99];
+        let adder = vec![3, 100, 3, 101, 104, 7, 1, 100, 101, 100, 4, 100, 99];
+        let mut manager = Manager::new(3);
+        manager.spawn(Amplifier::new(doubler, vec![]), Framing::Addressed(2));
+        manager.spawn(Amplifier::new(adder, vec![]), Framing::Addressed(1));
+        manager.run();
+        assert_eq!(Some(&Status::Blocked), manager.status(0));
+        manager.send(0, &[5]);
+        manager.run();
+        assert!(manager.is_finished());
+        assert_eq!(vec![Message { from: Some(1), to: Some(7), words: vec![15] }], manager.take_outbox());
+    }
+
+    #[test]
+    fn quantum_kill_and_faults() {
+        // an endless loop, a faulting program and a short one
+        let mut manager = Manager::new(5);
+        let spinner = manager.spawn(Amplifier::new(vec![1105, 1, 0], vec![]), Framing::Stream(None));
+        let faulty = manager.spawn(Amplifier::new(vec![1101, 1, 1, 5, 12, 0], vec![]), Framing::Stream(None));
+        let short = manager.spawn(Amplifier::new(vec![1101, 1, 1, 5, 99], vec![]), Framing::Stream(None));
+        for _ in 0..4 {
+            manager.run_round();
+        }
+        assert_eq!(Some(Usage { instructions: 20, slices: 4 }), manager.usage(spinner));
+        assert_eq!(Some(&Status::Exited(Exit::Faulted(Fault::IllegalOpcode { address: 4, opcode: 12 }))),
+                   manager.status(faulty));
+        assert_eq!(Some(Usage { instructions: 2, slices: 1 }), manager.usage(short));
+        assert!(!manager.is_finished());
+        assert!(manager.kill(spinner));
+        assert!(!manager.kill(spinner));
+        manager.run();
+        assert_eq!(Some(&Status::Exited(Exit::Killed)), manager.status(spinner));
+        assert!(manager.is_finished());
+    }
+
+    #[test]
+    fn network() {
+        // day 23 part 1, the first packet sent to address 255
+        let program = intcode::read_data("../nic/23");
+        let mut manager = Manager::new(100);
+        for address in 0..50 {
+            let mut amp = Amplifier::new(program.clone(), vec![address]);
+            amp.set_network_mode(true);
+            manager.spawn(amp, Framing::Addressed(2));
+        }
+        let mut outbox = Vec::new();
+        while outbox.is_empty() {
+            manager.run_round();
+            outbox = manager.take_outbox();
+        }
+        assert_eq!(Some(255), outbox[0].to);
+        assert_eq!(24106, outbox[0].words[1]);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::intcode::Amplifier;
+use std::fmt;
+
+// Narrows down which memory cells hold some quantity of a running program. Every filter
+// compares the memory of now with the snapshot of the previous filter (or the start).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Filter {
+    Equals(isize),
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    ChangedBy(isize),
+}
+
+impl Filter {
+    fn keeps(self, before: isize, now: isize) -> bool {
+        match self {
+            Filter::Equals(value) => now == value,
+            Filter::Changed => now != before,
+            Filter::Unchanged => now == before,
+            Filter::Increased => now > before,
+            Filter::Decreased => now < before,
+            Filter::ChangedBy(delta) => now.wrapping_sub(before) == delta,
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Filter> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["=", value] => value.parse().ok().map(Filter::Equals),
+            ["changed"] => Some(Filter::Changed),
+            ["unchanged"] => Some(Filter::Unchanged),
+            ["up"] => Some(Filter::Increased),
+            ["down"] => Some(Filter::Decreased),
+            ["by", delta] => delta.parse().ok().map(Filter::ChangedBy),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Filter::Equals(value) => write!(f, "= {}", value),
+            Filter::Changed => write!(f, "changed"),
+            Filter::Unchanged => write!(f, "unchanged"),
+            Filter::Increased => write!(f, "up"),
+            Filter::Decreased => write!(f, "down"),
+            Filter::ChangedBy(delta) => write!(f, "by {}", delta),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Search {
+    candidates: Vec<usize>,
+    snapshot: Vec<isize>,
+}
+
+impl Search {
+    // every address is a candidate at first
+    pub fn new(amp: &Amplifier) -> Search {
+        let snapshot = amp.get_program_clone();
+        Search { candidates: (0..snapshot.len()).collect(), snapshot }
+    }
+
+    // returns the number of remaining candidates
+    pub fn filter(&mut self, amp: &Amplifier, filter: Filter) -> usize {
+        let memory = amp.get_program_clone();
+        let snapshot = &self.snapshot;
+        self.candidates.retain(|address| match memory.get(*address) {
+            Some(now) => filter.keeps(snapshot[*address], *now),
+            None => false,
+        });
+        self.snapshot = memory;
+        self.candidates.len()
+    }
+
+    pub fn candidates(&self) -> &[usize] {
+        &self.candidates
+    }
+
+    // candidates with their values at the last snapshot
+    pub fn values(&self) -> Vec<(usize, isize)> {
+        self.candidates.iter().map(|address| (*address, self.snapshot[*address])).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intcode::{read_data, Amplifier, Event};
+    use crate::patch;
+    use crate::search::{Filter, Search};
+
+    #[derive(Default)]
+    struct Game {
+        ball: isize,
+        paddle: isize,
+        score: isize,
+    }
+
+    // runs until the game asks for the joystick, which follows the ball
+    fn frame(amp: &mut Amplifier, game: &mut Game) {
+        let mut triple = Vec::new();
+        loop {
+            match amp.run_until_event(false) {
+                Ok(Event::Output(value)) => triple.push(value),
+                Ok(Event::NeedInput) => break,
+                event => panic!("game over: {:?}", event),
+            }
+            if let [x, y, tile] = triple[..] {
+                match (x, y, tile) {
+                    (-1, 0, points) => game.score = points,
+                    (x, _, 3) => game.paddle = x,
+                    (x, _, 4) => game.ball = x,
+                    _ => (),
+                }
+                triple.clear();
+            }
+        }
+        amp.push_input((game.ball - game.paddle).signum());
+    }
+
+    #[test]
+    fn day13_ball_paddle_and_score() {
+        let mut amp = Amplifier::new(read_data("../13Dec/program"), vec![]);
+        amp.apply_patch(&patch::named("free-play").unwrap()).unwrap();
+        let mut game = Game::default();
+        frame(&mut amp, &mut game);
+        let (mut balls, mut paddles, mut scores) = (Search::new(&amp), Search::new(&amp), Search::new(&amp));
+        balls.filter(&amp, Filter::Equals(game.ball));
+        paddles.filter(&amp, Filter::Equals(game.paddle));
+        scores.filter(&amp, Filter::Equals(game.score));
+        let narrowed = |searches: &[&Search]| searches.iter().all(|search| search.candidates().len() == 1);
+        while !narrowed(&[&balls, &paddles, &scores]) {
+            let (ball, score) = (game.ball, game.score);
+            frame(&mut amp, &mut game);
+            balls.filter(&amp, Filter::ChangedBy(game.ball - ball));
+            paddles.filter(&amp, Filter::Equals(game.paddle));
+            scores.filter(&amp, if game.score > score { Filter::Increased } else { Filter::Unchanged });
+        }
+        assert_eq!(vec![(388, game.ball)], balls.values());
+        assert_eq!(vec![(392, game.paddle)], paddles.values());
+        assert_eq!(vec![(386, game.score)], scores.values());
+        assert_eq!(Some(Filter::ChangedBy(-1)), Filter::parse("by -1"));
+        assert_eq!(None, Filter::parse("by"));
+    }
+
+    #[test]
+    fn deltas_of_extreme_values_wrap() {
+        assert!(Filter::ChangedBy(1).keeps(isize::MAX, isize::MIN));
+        assert!(!Filter::ChangedBy(0).keeps(isize::MIN, isize::MAX));
+    }
+}
@@ -0,0 +1,357 @@
+use crate::image;
+use crate::intcode::{Amplifier, Event};
+use crate::patch::{self, Patch};
+use crate::search::{Filter, Search};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const HELP: &str = "\
+input <n>...        queue numbers
+ascii <text>        queue text followed by a newline
+step                run one instruction
+run                 run until an output, an input request or halt
+go                  run until an input request or halt
+outputs             show and clear the collected outputs
+mode num|text       how outputs are shown
+peek <addr> [n]     show n words of memory
+poke <addr> <v>...  patch memory
+patch <name|file>   apply a named patch or all patches of a file
+unpatch <name>      revert an applied patch
+search start        remember memory for a new search
+search <filter>     keep cells that are = <n>, changed, unchanged, up, down or by <n>
+search list         show the remaining cells
+regs                show ip, relative base and queued inputs
+save <name>         snapshot the vm
+restore <name>      go back to a snapshot
+write <file>        write memory as a text program
+source <file>       run the commands of a file
+quit";
+
+// how deep source may nest, a file that sources itself stops here
+const MAX_SOURCE_DEPTH: usize = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Display {
+    Numbers,
+    Text,
+}
+
+pub struct Shell {
+    amp: Amplifier,
+    outputs: Vec<isize>,
+    display: Display,
+    snapshots: HashMap<String, (Amplifier, Vec<isize>)>,
+    patches: Vec<Patch>,
+    search: Option<Search>,
+    depth: usize,
+    pub finished: bool,
+}
+
+fn parse_numbers<'a, I: Iterator<Item = &'a str>>(words: I) -> Result<Vec<isize>, String> {
+    words.map(|w| w.parse().map_err(|_| format!("not a number: {}", w))).collect()
+}
+
+fn parse_address(word: Option<&str>) -> Result<usize, String> {
+    let word = word.ok_or("missing address")?;
+    word.parse().map_err(|_| format!("not an address: {}", word))
+}
+
+impl Shell {
+    pub fn new(amp: Amplifier) -> Shell {
+        Shell { amp, outputs: Vec::new(), display: Display::Numbers, snapshots: HashMap::new(), patches: Vec::new(),
+                search: None, depth: 0, finished: false }
+    }
+
+    pub fn amplifier(&self) -> &Amplifier {
+        &self.amp
+    }
+
+    // text mode prints printable ASCII as characters and everything else as a number
+    fn show(&self, values: &[isize]) -> String {
+        match self.display {
+            Display::Numbers => values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(","),
+            Display::Text => values
+                .iter()
+                .map(|v| match *v {
+                    10 | 32..=126 => (*v as u8 as char).to_string(),
+                    _ => format!("<{}>", v),
+                })
+                .collect(),
+        }
+    }
+
+    fn event(&mut self, event: Event) -> String {
+        match event {
+            Event::Output(value) => {
+                self.outputs.push(value);
+                format!("output {}", self.show(&[value]))
+            }
+            Event::NeedInput => "waiting for input".to_string(),
+            Event::Halted => "halted".to_string(),
+            Event::Watchpoint(hit) => format!("watchpoint {} at {}, ip {}", hit.id, hit.address, hit.ip),
+        }
+    }
+
+    fn go(&mut self) -> Result<String, String> {
+        let start = self.outputs.len();
+        loop {
+            match self.amp.run_until_event(false).map_err(|f| f.to_string())? {
+                Event::Output(value) => self.outputs.push(value),
+                event => {
+                    let produced = self.show(&self.outputs[start..]);
+                    let status = self.event(event);
+                    return Ok(if produced.is_empty() { status } else { format!("{}\n{}", produced, status) });
+                }
+            }
+        }
+    }
+
+    // empty lines and lines starting with # are ignored
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(String::new());
+        }
+        let (command, rest) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim_start()),
+            None => (line, ""),
+        };
+        let mut words = rest.split_whitespace();
+        match command {
+            "input" => {
+                let values = parse_numbers(words)?;
+                self.amp.push_input_vec(values);
+                Ok(String::new())
+            }
+            "ascii" => {
+                let mut values: Vec<isize> = rest.bytes().map(isize::from).collect();
+                values.push(10);
+                self.amp.push_input_vec(values);
+                Ok(String::new())
+            }
+            "step" => match self.amp.step(false).map_err(|f| f.to_string())? {
+                Some(event) => Ok(self.event(event)),
+                None => Ok(format!("ip {}", self.amp.ip())),
+            },
+            "run" => {
+                let event = self.amp.run_until_event(false).map_err(|f| f.to_string())?;
+                Ok(self.event(event))
+            }
+            "go" => self.go(),
+            "outputs" => {
+                let shown = self.show(&self.outputs);
+                self.outputs.clear();
+                Ok(shown)
+            }
+            "mode" => {
+                self.display = match words.next() {
+                    Some("num") => Display::Numbers,
+                    Some("text") => Display::Text,
+                    _ => return Err("usage: mode num|text".to_string()),
+                };
+                Ok(String::new())
+            }
+            "peek" => {
+                let address = parse_address(words.next())?;
+                let count = match words.next() {
+                    Some(n) => n.parse().map_err(|_| format!("not a count: {}", n))?,
+                    None => 1,
+                };
+                let end = address.saturating_add(count).min(self.amp.memory_size());
+                if address >= end {
+                    return Err(format!("address {} out of bounds", address));
+                }
+                let values: Vec<String> = (address..end).filter_map(|a| self.amp.cell(a)).map(|v| v.to_string())
+                    .collect();
+                Ok(format!("{}: {}", address, values.join(",")))
+            }
+            "poke" => {
+                let address = parse_address(words.next())?;
+                let values = parse_numbers(words)?;
+                let memory = self.amp.memory_mut();
+                let end = match address.checked_add(values.len()) {
+                    Some(end) if end <= memory.len() => end,
+                    _ => return Err(format!("address {} out of bounds", address)),
+                };
+                memory[address..end].copy_from_slice(&values);
+                Ok(String::new())
+            }
+            "patch" if !rest.is_empty() => {
+                let patches = if Path::new(rest).is_file() {
+                    patch::load(rest).map_err(|e| e.to_string())?
+                } else {
+                    vec![patch::named(rest).map_err(|e| e.to_string())?]
+                };
+                for patch in patches {
+                    self.amp.apply_patch(&patch).map_err(|e| e.to_string())?;
+                    self.patches.push(patch);
+                }
+                Ok(String::new())
+            }
+            "unpatch" => {
+                let index = self.patches.iter().rposition(|p| p.name == rest)
+                    .ok_or_else(|| format!("patch {:?} is not applied", rest))?;
+                self.amp.revert_patch(&self.patches[index]).map_err(|e| e.to_string())?;
+                self.patches.remove(index);
+                Ok(String::new())
+            }
+            "search" => match rest {
+                "start" => {
+                    let search = Search::new(&self.amp);
+                    let candidates = search.candidates().len();
+                    self.search = Some(search);
+                    Ok(format!("{} candidates", candidates))
+                }
+                "list" => {
+                    let search = self.search.as_ref().ok_or("no search started")?;
+                    let values: Vec<String> =
+                        search.values().iter().take(20).map(|(address, value)| format!("{}: {}", address, value))
+                            .collect();
+                    Ok(values.join("\n"))
+                }
+                _ => {
+                    let filter = Filter::parse(rest).ok_or_else(|| format!("not a filter: {}", rest))?;
+                    let search = self.search.as_mut().ok_or("no search started")?;
+                    Ok(format!("{} candidates", search.filter(&self.amp, filter)))
+                }
+            },
+            "regs" => Ok(format!("ip {} rb {} inputs {}",
+                                 self.amp.ip(), self.amp.relative_base(), self.amp.pending_input())),
+            "save" if !rest.is_empty() => {
+                self.snapshots.insert(rest.to_string(), (self.amp.clone(), self.outputs.clone()));
+                Ok(String::new())
+            }
+            "restore" => match self.snapshots.get(rest) {
+                Some((amp, outputs)) => {
+                    self.amp = amp.clone();
+                    self.outputs = outputs.clone();
+                    Ok(String::new())
+                }
+                None => Err(format!("no snapshot named {:?}", rest)),
+            },
+            "write" if !rest.is_empty() => {
+                fs::write(rest, image::to_text(&self.amp.get_program_clone())).map_err(|e| format!("{}: {}", rest, e))?;
+                Ok(String::new())
+            }
+            "source" if !rest.is_empty() => {
+                if self.depth == MAX_SOURCE_DEPTH {
+                    return Err(format!("source nested more than {} deep", MAX_SOURCE_DEPTH));
+                }
+                let script = fs::read_to_string(rest).map_err(|e| format!("{}: {}", rest, e))?;
+                self.depth += 1;
+                let result = self.script(&script);
+                self.depth -= 1;
+                result
+            }
+            "help" => Ok(HELP.to_string()),
+            "quit" | "exit" => {
+                self.finished = true;
+                Ok(String::new())
+            }
+            _ => Err(format!("unknown command {:?}, try help", line)),
+        }
+    }
+
+    // stops at the first failing line
+    pub fn script(&mut self, script: &str) -> Result<String, String> {
+        let mut transcript = Vec::new();
+        for (number, line) in script.lines().enumerate() {
+            let result = self.execute(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            if !result.is_empty() {
+                transcript.push(result);
+            }
+            if self.finished {
+                break;
+            }
+        }
+        Ok(transcript.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intcode::{read_data, Amplifier};
+    use crate::shell::Shell;
+
+    #[test]
+    fn day9_session() {
+        let mut shell = Shell::new(Amplifier::new(read_data("9"), vec![]));
+        assert_eq!(Ok("waiting for input".to_string()), shell.execute("run"));
+        shell.execute("save start").unwrap();
+        shell.execute("input 1").unwrap();
+        assert_eq!(Ok("3497884671\nhalted".to_string()), shell.execute("go"));
+        shell.execute("restore start").unwrap();
+        shell.execute("input 2").unwrap();
+        assert_eq!(Ok("output 46470".to_string()), shell.execute("run"));
+        assert_eq!(Ok("46470".to_string()), shell.execute("outputs"));
+        assert_eq!(Ok(String::new()), shell.execute("outputs"));
+    }
+
+    #[test]
+    fn ascii_and_patching() {
+        // echoes what it reads until it reads a 0
+        let program = vec![3, 20, 1006, 20, 11, 4, 20, 1105, 1, 0, 0, 99];
+        let mut shell = Shell::new(Amplifier::new(program, vec![]));
+        let script = "
+            # text in, text out
+            mode text
+            ascii hi
+            input 7
+            go
+            regs
+            poke 11 104 33 99
+            peek 11 3
+            input 0
+            go
+        ";
+        assert_eq!(Ok("hi\n<7>\nwaiting for input\nip 0 rb 0 inputs 0\n11: 104,33,99\n!\nhalted".to_string()),
+                   shell.script(script));
+        assert!(shell.execute("poke x 1").is_err());
+        assert!(shell.execute(&format!("poke {} 1 2", usize::MAX)).is_err());
+        assert_eq!(Err("line 2: unknown command \"bogus\", try help".to_string()), shell.script("mode num\nbogus"));
+    }
+
+    #[test]
+    fn patches() {
+        let mut shell = Shell::new(Amplifier::new(read_data("../13Dec/program"), vec![]));
+        assert_eq!(Ok("0: 2".to_string()), shell.script("patch free-play\npeek 0"));
+        assert_eq!(Err("patch free-play: expected 1 at 0, found 2".to_string()), shell.execute("patch free-play"));
+        assert_eq!(Ok("0: 1".to_string()), shell.script("unpatch free-play\npeek 0"));
+        assert!(shell.execute("unpatch free-play").is_err());
+        assert!(shell.execute("patch nonsense").is_err());
+    }
+
+    #[test]
+    fn search() {
+        // counts [20] up by 3 per input
+        let program = vec![3, 21, 1001, 20, 3, 20, 1105, 1, 0];
+        let mut shell = Shell::new(Amplifier::new(program, vec![]));
+        assert!(shell.execute("search up").is_err());
+        shell.script("search start\ninput 1\nrun").unwrap();
+        assert_eq!(Ok("2 candidates".to_string()), shell.execute("search changed"));
+        shell.script("input 1\nrun").unwrap();
+        assert_eq!(Ok("1 candidates".to_string()), shell.execute("search by 3"));
+        assert_eq!(Ok("20: 6".to_string()), shell.execute("search list"));
+        assert_eq!(Err("not a filter: bigger".to_string()), shell.execute("search bigger"));
+    }
+
+    #[test]
+    fn source_recursion_is_limited() {
+        let path = std::env::temp_dir().join(format!("intcode-shell-{}.txt", std::process::id()));
+        std::fs::write(&path, format!("source {}\n", path.display())).unwrap();
+        let mut shell = Shell::new(Amplifier::new(vec![99], vec![]));
+        let result = shell.execute(&format!("source {}", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.unwrap_err().ends_with("source nested more than 16 deep"));
+        assert_eq!(Ok("0: 99".to_string()), shell.execute("peek 0"));
+    }
+
+    #[test]
+    fn static_programs_show_their_padding() {
+        static PROGRAM: [i64; 2] = [104, 99];
+        let mut shell = Shell::new(Amplifier::from_static(&PROGRAM, vec![]));
+        assert_eq!(Ok("1: 99,0,0".to_string()), shell.execute("peek 1 3"));
+        assert!(shell.execute(&format!("peek {}", 2 + 40960)).is_err());
+    }
+}
@@ -0,0 +1,675 @@
+use crate::intcode::PADDING;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+// Symbolic execution of intcode programs. Memory cells and inputs may hold
+// symbols; arithmetic builds expressions and conditional jumps fork the
+// state with path constraints. Addresses, opcodes and jump targets must be
+// concrete, so symbolic ones are resolved by forking over their (small)
+// range of values.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Const(isize),
+    Sym(usize, Rc<str>),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+    LessThan(Rc<Expr>, Rc<Expr>),
+    Equals(Rc<Expr>, Rc<Expr>),
+    Load(Rc<Expr>, Rc<Vec<Rc<Expr>>>),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Const(c) => write!(f, "{}", c),
+            Expr::Sym(_, name) => write!(f, "{}", name),
+            Expr::Add(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Mul(a, b) => write!(f, "({} * {})", a, b),
+            Expr::LessThan(a, b) => write!(f, "({} < {})", a, b),
+            Expr::Equals(a, b) => write!(f, "({} == {})", a, b),
+            Expr::Load(addr, _) => write!(f, "mem[{}]", addr),
+        }
+    }
+}
+
+fn constant(value: isize) -> Rc<Expr> {
+    Rc::new(Expr::Const(value))
+}
+
+fn add(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+    match (&*a, &*b) {
+        (Expr::Const(x), Expr::Const(y)) => constant(x.wrapping_add(*y)),
+        (Expr::Const(0), _) => b,
+        (_, Expr::Const(0)) => a,
+        _ => Rc::new(Expr::Add(a, b)),
+    }
+}
+
+fn mul(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+    match (&*a, &*b) {
+        (Expr::Const(x), Expr::Const(y)) => constant(x.wrapping_mul(*y)),
+        (Expr::Const(0), _) | (_, Expr::Const(0)) => constant(0),
+        (Expr::Const(1), _) => b,
+        (_, Expr::Const(1)) => a,
+        _ => Rc::new(Expr::Mul(a, b)),
+    }
+}
+
+fn less_than(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+    match (&*a, &*b) {
+        (Expr::Const(x), Expr::Const(y)) => constant((x < y) as isize),
+        _ => Rc::new(Expr::LessThan(a, b)),
+    }
+}
+
+fn equals(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+    match (&*a, &*b) {
+        (Expr::Const(x), Expr::Const(y)) => constant((x == y) as isize),
+        _ => Rc::new(Expr::Equals(a, b)),
+    }
+}
+
+type Assignment = BTreeMap<usize, isize>;
+
+impl Expr {
+    pub fn eval(&self, assignment: &Assignment) -> Option<isize> {
+        match self {
+            Expr::Const(c) => Some(*c),
+            Expr::Sym(id, _) => assignment.get(id).cloned(),
+            Expr::Add(a, b) => Some(a.eval(assignment)?.wrapping_add(b.eval(assignment)?)),
+            Expr::Mul(a, b) => Some(a.eval(assignment)?.wrapping_mul(b.eval(assignment)?)),
+            Expr::LessThan(a, b) => Some((a.eval(assignment)? < b.eval(assignment)?) as isize),
+            Expr::Equals(a, b) => Some((a.eval(assignment)? == b.eval(assignment)?) as isize),
+            Expr::Load(addr, memory) => {
+                let addr = addr.eval(assignment)?;
+                if addr < 0 {
+                    return None;
+                }
+                match memory.get(addr as usize) {
+                    Some(cell) => cell.eval(assignment),
+                    None => Some(0),
+                }
+            }
+        }
+    }
+
+    fn symbols(&self, into: &mut BTreeSet<usize>) {
+        match self {
+            Expr::Const(_) => {}
+            Expr::Sym(id, _) => {
+                into.insert(*id);
+            }
+            Expr::Add(a, b) | Expr::Mul(a, b) | Expr::LessThan(a, b) | Expr::Equals(a, b) => {
+                a.symbols(into);
+                b.symbols(into);
+            }
+            Expr::Load(addr, memory) => {
+                addr.symbols(into);
+                for cell in memory.iter() {
+                    cell.symbols(into);
+                }
+            }
+        }
+    }
+
+    // Coefficients and constant of the expression if it is linear in the
+    // symbols that are not yet assigned.
+    fn linear(&self, assignment: &Assignment) -> Option<(BTreeMap<usize, i128>, i128)> {
+        if let Some(value) = self.eval(assignment) {
+            return Some((BTreeMap::new(), value as i128));
+        }
+        match self {
+            Expr::Sym(id, _) => {
+                let mut coefficients = BTreeMap::new();
+                coefficients.insert(*id, 1);
+                Some((coefficients, 0))
+            }
+            Expr::Add(a, b) => {
+                let (mut ca, ka) = a.linear(assignment)?;
+                let (cb, kb) = b.linear(assignment)?;
+                for (id, c) in cb {
+                    *ca.entry(id).or_insert(0) += c;
+                }
+                Some((ca, ka + kb))
+            }
+            Expr::Mul(a, b) => {
+                let (ca, ka) = a.linear(assignment)?;
+                let (cb, kb) = b.linear(assignment)?;
+                let (coefficients, k, factor) = match (ca.is_empty(), cb.is_empty()) {
+                    (true, _) => (cb, kb, ka),
+                    (_, true) => (ca, ka, kb),
+                    _ => return None,
+                };
+                Some((coefficients.into_iter().map(|(id, c)| (id, c * factor)).collect(), k * factor))
+            }
+            Expr::Load(addr, memory) => {
+                let addr = addr.eval(assignment)?;
+                match memory.get(addr as usize) {
+                    Some(cell) if addr >= 0 => cell.linear(assignment),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub expr: Rc<Expr>,
+    pub nonzero: bool,
+}
+
+impl Constraint {
+    pub fn equals(expr: Rc<Expr>, value: isize) -> Constraint {
+        Constraint { expr: equals(expr, constant(value)), nonzero: true }
+    }
+
+    fn holds(&self, assignment: &Assignment) -> Option<bool> {
+        self.expr.eval(assignment).map(|v| (v != 0) == self.nonzero)
+    }
+
+    // `a == b` and `e == 0` pin a symbol when they are linear.
+    fn equation(&self) -> Option<Rc<Expr>> {
+        match (&*self.expr, self.nonzero) {
+            (Expr::Equals(a, b), true) => Some(add(a.clone(), mul(b.clone(), constant(-1)))),
+            (_, false) => Some(self.expr.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.nonzero {
+            write!(f, "{} != 0", self.expr)
+        } else {
+            write!(f, "{} == 0", self.expr)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub domain: Range<isize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Solved {
+    Sat(Solution),
+    Unsat,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub values: Vec<(String, isize)>,
+}
+
+impl Solution {
+    pub fn get(&self, name: &str) -> Option<isize> {
+        self.values.iter().find(|(n, _)| n == name).map(|(_, v)| *v)
+    }
+}
+
+struct Solver<'a> {
+    symbols: &'a [Symbol],
+    constraints: &'a [Constraint],
+    budget: usize,
+    exhausted: bool,
+}
+
+impl<'a> Solver<'a> {
+    fn search(&mut self, assignment: &mut Assignment, free: &[usize]) -> bool {
+        if self.budget == 0 {
+            self.exhausted = true;
+            return false;
+        }
+        self.budget -= 1;
+        for constraint in self.constraints.iter() {
+            if constraint.holds(assignment) == Some(false) {
+                return false;
+            }
+        }
+        let unassigned: Vec<usize> = free.iter().cloned().filter(|id| !assignment.contains_key(id)).collect();
+        if unassigned.is_empty() {
+            return true;
+        }
+        for constraint in self.constraints.iter() {
+            let equation = match constraint.equation() {
+                Some(equation) => equation,
+                None => continue,
+            };
+            if let Some((coefficients, k)) = equation.linear(assignment) {
+                let open: Vec<(&usize, &i128)> = coefficients.iter().filter(|(_, c)| **c != 0).collect();
+                if open.len() != 1 {
+                    continue;
+                }
+                let (id, c) = (*open[0].0, *open[0].1);
+                if k % c != 0 {
+                    return false;
+                }
+                let value = (-k / c) as isize;
+                if !self.symbols[id].domain.contains(&value) {
+                    return false;
+                }
+                assignment.insert(id, value);
+                if self.search(assignment, free) {
+                    return true;
+                }
+                assignment.remove(&id);
+                return false;
+            }
+        }
+        let id = *unassigned
+            .iter()
+            .min_by_key(|id| self.symbols[**id].domain.len())
+            .unwrap();
+        for value in self.symbols[id].domain.clone() {
+            assignment.insert(id, value);
+            if self.search(assignment, free) {
+                return true;
+            }
+            if self.exhausted {
+                break;
+            }
+        }
+        assignment.remove(&id);
+        false
+    }
+}
+
+pub fn solve(symbols: &[Symbol], constraints: &[Constraint], budget: usize) -> Solved {
+    let mut free = BTreeSet::new();
+    for constraint in constraints.iter() {
+        constraint.expr.symbols(&mut free);
+    }
+    let free: Vec<usize> = free.into_iter().collect();
+    let mut solver = Solver { symbols, constraints, budget, exhausted: false };
+    let mut assignment = Assignment::new();
+    if solver.search(&mut assignment, &free) {
+        let values = assignment.iter().map(|(id, v)| (symbols[*id].name.clone(), *v)).collect();
+        Solved::Sat(Solution { values })
+    } else if solver.exhausted {
+        Solved::Unknown
+    } else {
+        Solved::Unsat
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Halted,
+    NeedInput,
+    Fault(String),
+    StepLimit,
+}
+
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub constraints: Vec<Constraint>,
+    pub outputs: Vec<Rc<Expr>>,
+    pub memory: Vec<Rc<Expr>>,
+    pub status: Status,
+}
+
+#[derive(Clone)]
+struct State {
+    ip: usize,
+    rb: isize,
+    memory: Vec<Rc<Expr>>,
+    inputs: VecDeque<Rc<Expr>>,
+    outputs: Vec<Rc<Expr>>,
+    constraints: Vec<Constraint>,
+    pinned: Vec<(Rc<Expr>, isize)>,
+    steps: usize,
+}
+
+enum Step {
+    Continue(State),
+    Fork(Vec<State>),
+    Done(State, Status),
+}
+
+pub struct Symbolic {
+    program: Vec<isize>,
+    symbols: Vec<Symbol>,
+    cells: Vec<(usize, usize)>,
+    inputs: Vec<Rc<Expr>>,
+    pub max_paths: usize,
+    pub max_steps: usize,
+    pub budget: usize,
+}
+
+impl Symbolic {
+    pub fn new(program: Vec<isize>) -> Symbolic {
+        Symbolic {
+            program,
+            symbols: Vec::new(),
+            cells: Vec::new(),
+            inputs: Vec::new(),
+            max_paths: 1024,
+            max_steps: 1_000_000,
+            budget: 1_000_000,
+        }
+    }
+
+    fn symbol(&mut self, name: &str, domain: Range<isize>) -> Rc<Expr> {
+        let id = self.symbols.len();
+        self.symbols.push(Symbol { name: name.to_string(), domain });
+        Rc::new(Expr::Sym(id, Rc::from(name)))
+    }
+
+    pub fn symbolic_cell(&mut self, address: usize, name: &str, domain: Range<isize>) {
+        assert!(address < self.memory_size(), "cell {} is outside of memory", address);
+        let id = self.symbols.len();
+        self.symbol(name, domain);
+        self.cells.push((address, id));
+    }
+
+    // the same amount of memory an Amplifier has
+    fn memory_size(&self) -> usize {
+        self.program.len() + PADDING
+    }
+
+    pub fn symbolic_input(&mut self, name: &str, domain: Range<isize>) {
+        let symbol = self.symbol(name, domain);
+        self.inputs.push(symbol);
+    }
+
+    pub fn push_input(&mut self, input: isize) {
+        self.inputs.push(constant(input));
+    }
+
+    pub fn explore(&self) -> Vec<Path> {
+        let mut memory: Vec<Rc<Expr>> = self.program.iter().map(|v| constant(*v)).collect();
+        for (address, id) in self.cells.iter() {
+            if *address >= memory.len() {
+                memory.resize(*address + 1, constant(0));
+            }
+            memory[*address] = Rc::new(Expr::Sym(*id, Rc::from(self.symbols[*id].name.as_str())));
+        }
+        let initial = State {
+            ip: 0,
+            rb: 0,
+            memory,
+            inputs: self.inputs.iter().cloned().collect(),
+            outputs: Vec::new(),
+            constraints: Vec::new(),
+            pinned: Vec::new(),
+            steps: 0,
+        };
+
+        let mut paths = Vec::new();
+        let mut pending = vec![initial];
+        while let Some(state) = pending.pop() {
+            if paths.len() + pending.len() >= self.max_paths {
+                paths.push(self.finish(state, Status::StepLimit));
+                continue;
+            }
+            match self.step(state) {
+                Step::Continue(state) => pending.push(state),
+                Step::Fork(states) => {
+                    for state in states {
+                        if solve(&self.symbols, &state.constraints, self.budget / 100) != Solved::Unsat {
+                            pending.push(state);
+                        }
+                    }
+                }
+                Step::Done(state, status) => paths.push(self.finish(state, status)),
+            }
+        }
+        paths
+    }
+
+    pub fn solve<F>(&self, goal: F) -> Option<Solution>
+    where
+        F: Fn(&Path) -> Option<Constraint>,
+    {
+        for path in self.explore() {
+            if path.status != Status::Halted && path.status != Status::NeedInput {
+                continue;
+            }
+            let target = match goal(&path) {
+                Some(target) => target,
+                None => continue,
+            };
+            let mut constraints = path.constraints.clone();
+            constraints.push(target);
+            if let Solved::Sat(solution) = solve(&self.symbols, &constraints, self.budget) {
+                return Some(solution);
+            }
+        }
+        None
+    }
+
+    pub fn solve_memory(&self, address: usize, value: isize) -> Option<Solution> {
+        self.solve(|path| path.memory.get(address).map(|cell| Constraint::equals(cell.clone(), value)))
+    }
+
+    pub fn solve_output(&self, index: usize, value: isize) -> Option<Solution> {
+        self.solve(|path| path.outputs.get(index).map(|out| Constraint::equals(out.clone(), value)))
+    }
+
+    fn finish(&self, state: State, status: Status) -> Path {
+        Path { constraints: state.constraints, outputs: state.outputs, memory: state.memory, status }
+    }
+
+    // Every value the expression can take under the symbol domains, or None
+    // if there are too many combinations to enumerate.
+    fn values(&self, expr: &Expr) -> Option<Vec<isize>> {
+        let mut ids = BTreeSet::new();
+        expr.symbols(&mut ids);
+        let ids: Vec<usize> = ids.into_iter().collect();
+        let mut combinations: usize = 1;
+        for id in ids.iter() {
+            combinations = combinations.saturating_mul(self.symbols[*id].domain.len());
+        }
+        if combinations > 4096 {
+            return None;
+        }
+        let mut values = BTreeSet::new();
+        let mut assignment = Assignment::new();
+        for mut n in 0..combinations {
+            for id in ids.iter() {
+                let domain = &self.symbols[*id].domain;
+                assignment.insert(*id, domain.start + (n % domain.len()) as isize);
+                n /= domain.len();
+            }
+            if let Some(v) = expr.eval(&assignment) {
+                values.insert(v);
+            }
+        }
+        Some(values.into_iter().collect())
+    }
+
+    fn concrete(&self, state: &State, expr: &Rc<Expr>) -> Result<isize, Vec<State>> {
+        if let Expr::Const(c) = **expr {
+            return Ok(c);
+        }
+        if let Some((_, v)) = state.pinned.iter().find(|(e, _)| Rc::ptr_eq(e, expr) || **e == **expr) {
+            return Ok(*v);
+        }
+        let values = match self.values(expr) {
+            Some(values) if values.len() <= 64 => values,
+            _ => return Err(vec![]),
+        };
+        Err(values
+            .into_iter()
+            .map(|v| {
+                let mut fork = state.clone();
+                fork.constraints.push(Constraint::equals(expr.clone(), v));
+                fork.pinned.push((expr.clone(), v));
+                fork
+            })
+            .collect())
+    }
+
+    fn load(&self, state: &State, address: &Rc<Expr>) -> Rc<Expr> {
+        match **address {
+            Expr::Const(a) if a >= 0 => state.memory.get(a as usize).cloned().unwrap_or_else(|| constant(0)),
+            _ => Rc::new(Expr::Load(address.clone(), Rc::new(state.memory.clone()))),
+        }
+    }
+
+    fn step(&self, mut state: State) -> Step {
+        macro_rules! concrete {
+            ($expr:expr) => {
+                match self.concrete(&state, &$expr) {
+                    Ok(v) => v,
+                    Err(forks) if forks.is_empty() => {
+                        let message = format!("unresolvable symbolic value {} at {}", $expr, state.ip);
+                        return Step::Done(state, Status::Fault(message));
+                    }
+                    Err(forks) => return Step::Fork(forks),
+                }
+            };
+        }
+
+        if state.steps >= self.max_steps {
+            return Step::Done(state, Status::StepLimit);
+        }
+        let ip = state.ip;
+        let word = self.load(&state, &constant(ip as isize));
+        let word = concrete!(word);
+        let opcode = word % 100;
+        let arity = match opcode {
+            1 | 2 | 7 | 8 => 3,
+            3 | 4 | 9 => 1,
+            5 | 6 => 2,
+            99 => return Step::Done(state, Status::Halted),
+            _ => return Step::Done(state, Status::Fault(format!("illegal opcode {} at {}", opcode, ip))),
+        };
+        let mut addresses = Vec::new();
+        let mut values = Vec::new();
+        for i in 0..arity {
+            let mode = (word / [100, 1000, 10000][i]) % 10;
+            let param = self.load(&state, &constant((ip + 1 + i) as isize));
+            let address = match mode {
+                0 => param.clone(),
+                1 => constant(-1),
+                2 => add(constant(state.rb), param.clone()),
+                _ => return Step::Done(state, Status::Fault(format!("invalid mode in {} at {}", word, ip))),
+            };
+            values.push(if mode == 1 { param } else { self.load(&state, &address) });
+            addresses.push((mode, address));
+        }
+
+        let mut write = None;
+        let mut next = ip + 1 + arity;
+        match opcode {
+            1 => write = Some((2, add(values[0].clone(), values[1].clone()))),
+            2 => write = Some((2, mul(values[0].clone(), values[1].clone()))),
+            7 => write = Some((2, less_than(values[0].clone(), values[1].clone()))),
+            8 => write = Some((2, equals(values[0].clone(), values[1].clone()))),
+            3 => match state.inputs.pop_front() {
+                Some(input) => write = Some((0, input)),
+                None => return Step::Done(state, Status::NeedInput),
+            },
+            4 => state.outputs.push(values[0].clone()),
+            9 => state.rb = state.rb.wrapping_add(concrete!(values[0])),
+            _ => {
+                let target = concrete!(values[1]);
+                let jump = |v: isize| if opcode == 5 { v != 0 } else { v == 0 };
+                let condition = values[0].clone();
+                if let Expr::Const(v) = *condition {
+                    if jump(v) {
+                        next = target as usize;
+                    }
+                } else {
+                    let mut taken = state.clone();
+                    taken.constraints.push(Constraint { expr: condition.clone(), nonzero: opcode == 5 });
+                    taken.ip = target as usize;
+                    taken.steps += 1;
+                    state.constraints.push(Constraint { expr: condition, nonzero: opcode == 6 });
+                    state.ip = next;
+                    state.steps += 1;
+                    return Step::Fork(vec![state, taken]);
+                }
+            }
+        }
+        if let Some((param, value)) = write {
+            let (mode, address) = addresses[param].clone();
+            if mode == 1 {
+                return Step::Done(state, Status::Fault(format!("immediate write at {}", ip)));
+            }
+            let address = concrete!(address);
+            if address < 0 {
+                return Step::Done(state, Status::Fault(format!("negative address {} at {}", address, ip)));
+            }
+            let address = address as usize;
+            if address >= self.memory_size() {
+                return Step::Done(state, Status::Fault(format!("address {} out of bounds at {}", address, ip)));
+            }
+            if address >= state.memory.len() {
+                state.memory.resize(address + 1, constant(0));
+            }
+            state.memory[address] = value;
+        }
+        state.ip = next;
+        state.steps += 1;
+        state.pinned.clear();
+        Step::Continue(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symbolic::{Status, Symbolic};
+
+    #[test]
+    fn day2_part2_as_query() {
+        let program = crate::intcode::read_data("2");
+        let mut symbolic = Symbolic::new(program.clone());
+        symbolic.symbolic_cell(1, "noun", 0..100);
+        symbolic.symbolic_cell(2, "verb", 0..100);
+        let solution = symbolic.solve_memory(0, 19690720).unwrap();
+        let noun = solution.get("noun").unwrap();
+        let verb = solution.get("verb").unwrap();
+
+        let mut computer = crate::intcode::Amplifier::new(program, vec![]);
+        assert_eq!(19690720, computer.run_program_in_compatibility_mode(noun, verb, false));
+    }
+
+    #[test]
+    fn branches_fork_and_solve_input() {
+        // outputs 1 if the input equals 8, 0 otherwise
+        let mut symbolic = Symbolic::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8]);
+        symbolic.symbolic_input("x", -100..100);
+        assert_eq!(Some(8), symbolic.solve_output(0, 1).unwrap().get("x"));
+    }
+
+    #[test]
+    fn jumps_on_symbols_explore_both_paths() {
+        // if input < 5 output 1 else output 2
+        let program = vec![3, 15, 1007, 15, 5, 16, 1005, 16, 12, 104, 2, 99, 104, 1, 99, 0, 0];
+        let mut symbolic = Symbolic::new(program);
+        symbolic.symbolic_input("x", 0..10);
+        let paths = symbolic.explore();
+        assert_eq!(2, paths.len());
+        assert!(paths.iter().all(|p| p.status == Status::Halted));
+        assert!(symbolic.solve_output(0, 2).unwrap().get("x").unwrap() >= 5);
+        assert!(symbolic.solve_output(0, 1).unwrap().get("x").unwrap() < 5);
+    }
+
+    #[test]
+    fn unsatisfiable_query() {
+        let mut symbolic = Symbolic::new(vec![3, 7, 1002, 7, 2, 7, 99, 0]);
+        symbolic.symbolic_input("x", 0..100);
+        assert_eq!(None, symbolic.solve_memory(7, 13));
+        assert_eq!(Some(6), symbolic.solve_memory(7, 12).unwrap().get("x"));
+    }
+
+    #[test]
+    fn large_constants_and_far_writes() {
+        // multiplies two large constants, then writes far beyond memory
+        let program = vec![1102, i64::MAX as isize, 3, 0, 4, 0, 1101, 1, 1, 1 << 40, 99];
+        let paths = Symbolic::new(program).explore();
+        assert_eq!(Some(i64::MAX.wrapping_mul(3) as isize), paths[0].outputs[0].eval(&Default::default()));
+        assert_eq!(Status::Fault(format!("address {} out of bounds at 6", 1u64 << 40)), paths[0].status);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::extension::Device;
+use std::collections::VecDeque;
+
+// lines kept after they scrolled off the screen
+const SCROLLBACK: usize = 1000;
+
+// Turns ASCII output into a screen of `height` lines. Lines that scroll off the top go to the
+// scroll-back, a blank line ends the current frame and starts a new, empty screen.
+// Outputs outside of ASCII are not drawn, they are collected as values (day 17 reports the
+// collected dust that way).
+#[derive(Debug, Clone)]
+pub struct Terminal {
+    height: usize,
+    screen: Vec<Vec<char>>,
+    row: usize,
+    column: usize,
+    scrollback: VecDeque<String>,
+    frames: Vec<Vec<String>>,
+    values: Vec<isize>,
+    // the last output ended a line
+    at_line_start: bool,
+}
+
+impl Terminal {
+    pub fn new(height: usize) -> Terminal {
+        assert!(height > 0, "a terminal needs at least one line");
+        Terminal {
+            height,
+            screen: vec![Vec::new()],
+            row: 0,
+            column: 0,
+            scrollback: VecDeque::new(),
+            frames: Vec::new(),
+            values: Vec::new(),
+            at_line_start: true,
+        }
+    }
+
+    pub fn write(&mut self, value: isize) {
+        match value {
+            10 => self.newline(),
+            13 => self.column = 0,
+            32..=126 => {
+                if self.row == self.height {
+                    self.scroll();
+                }
+                let line = &mut self.screen[self.row];
+                if line.len() <= self.column {
+                    line.resize(self.column + 1, ' ');
+                }
+                line[self.column] = value as u8 as char;
+                self.column += 1;
+                self.at_line_start = false;
+            }
+            _ => self.values.push(value),
+        }
+    }
+
+    pub fn write_all(&mut self, values: &[isize]) {
+        for value in values.iter() {
+            self.write(*value);
+        }
+    }
+
+    fn newline(&mut self) {
+        if self.at_line_start && self.column == 0 {
+            self.end_frame();
+            return;
+        }
+        self.at_line_start = true;
+        self.column = 0;
+        self.row += 1;
+        if self.row == self.screen.len() {
+            self.screen.push(Vec::new());
+        }
+    }
+
+    // only happens once something is written below the last line, so a full screen
+    // followed by a newline stays complete
+    fn scroll(&mut self) {
+        let line: String = self.screen.remove(0).into_iter().collect();
+        self.scrollback.push_back(line);
+        if self.scrollback.len() > SCROLLBACK {
+            self.scrollback.pop_front();
+        }
+        self.row -= 1;
+    }
+
+    // empty frames from several blank lines in a row are dropped
+    fn end_frame(&mut self) {
+        let frame = self.screen();
+        if !frame.is_empty() {
+            self.frames.push(frame);
+        }
+        self.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.screen = vec![Vec::new()];
+        self.row = 0;
+        self.column = 0;
+        self.at_line_start = true;
+    }
+
+    // the lines on screen without the empty line the cursor sits on after a newline
+    pub fn screen(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.screen.iter().map(|line| line.iter().collect()).collect();
+        if lines.last().is_some_and(|line| line.is_empty()) && self.column == 0 {
+            lines.pop();
+        }
+        lines
+    }
+
+    pub fn text(&self) -> String {
+        self.screen().iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    // row and column of the cursor on screen
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.column)
+    }
+
+    pub fn scrollback(&self) -> &VecDeque<String> {
+        &self.scrollback
+    }
+
+    pub fn frames(&self) -> &[Vec<String>] {
+        &self.frames
+    }
+
+    pub fn take_frames(&mut self) -> Vec<Vec<String>> {
+        std::mem::take(&mut self.frames)
+    }
+
+    pub fn values(&self) -> &[isize] {
+        &self.values
+    }
+}
+
+// mapped into memory every write to the device is an output, reads give the cursor row and column
+impl Device for Terminal {
+    fn read(&mut self, offset: usize) -> isize {
+        match offset {
+            0 => self.row as isize,
+            _ => self.column as isize,
+        }
+    }
+
+    fn write(&mut self, _offset: usize, value: isize) {
+        Terminal::write(self, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intcode::{read_data, Amplifier};
+    use crate::terminal::Terminal;
+    use std::sync::{Arc, Mutex};
+
+    fn ascii(text: &str) -> Vec<isize> {
+        text.bytes().map(isize::from).collect()
+    }
+
+    #[test]
+    fn frames_and_scrollback() {
+        let mut terminal = Terminal::new(2);
+        terminal.write_all(&ascii("ab\ncd\nef"));
+        assert_eq!(vec!["cd", "ef"], terminal.screen());
+        assert_eq!(vec!["ab"], terminal.scrollback().iter().collect::<Vec<_>>());
+        assert_eq!((1, 2), terminal.cursor());
+
+        terminal.write_all(&ascii("\rE\n\n\nnext\n"));
+        terminal.write(1000);
+        assert_eq!(vec![vec!["cd", "Ef"]], terminal.frames());
+        assert_eq!("next\n", terminal.text());
+        assert_eq!(&[1000], terminal.values());
+        assert_eq!(1, terminal.take_frames().len());
+        assert!(terminal.frames().is_empty());
+    }
+
+    #[test]
+    fn day17_camera() {
+        let mut terminal = Terminal::new(100);
+        let mut computer = Amplifier::new(read_data("../17Dec/program"), vec![]);
+        while let Some(value) = computer.run_program_until_output(false) {
+            terminal.write(value);
+        }
+        let frame = &terminal.frames()[0];
+        assert!(frame.iter().all(|line| line.len() == frame[0].len()));
+        assert_eq!(1, frame.iter().map(|line| line.matches(|c| "^v<>".contains(c)).count()).sum::<usize>());
+    }
+
+    #[test]
+    fn mapped() {
+        // writes "hi" and a newline to the terminal at 100, then reads back the row
+        let program = vec![1101, 104, 0, 100, 1101, 105, 0, 100, 1101, 10, 0, 100, 4, 100, 99];
+        let terminal = Arc::new(Mutex::new(Terminal::new(10)));
+        let mut computer = Amplifier::new(program, vec![]);
+        computer.map_device(100..102, terminal.clone());
+        assert_eq!(Some(1), computer.run_program(false));
+        assert_eq!(vec!["hi"], terminal.lock().unwrap().screen());
+    }
+}
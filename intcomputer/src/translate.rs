@@ -0,0 +1,434 @@
+use crate::analysis::{self, Decoded, Param};
+use crate::intcode;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const NO_BLOCK: u32 = u32::MAX;
+
+// Runtime part of every translated program, a wrapper around intcode::Amplifier. Blocks that
+// were modified at runtime, addresses that are not the start of a block and instructions that
+// would fault run on the interpreter, so faults and I/O behave exactly like there.
+const RUNTIME: &str = r#"
+    use intcomputer::intcode::{self, Event};
+    use intcomputer::watch::{Access, Watchpoint};
+    use std::collections::VecDeque;
+    use std::convert::TryFrom;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    pub struct Amplifier {
+        amp: intcode::Amplifier,
+        // number of cells per block that differ from the translated image
+        dirty: Vec<u32>,
+        // (address, old, new) of the image cells the interpreter wrote
+        written: Arc<Mutex<Vec<(usize, isize, isize)>>>,
+        watch: usize,
+    }
+
+    // every copy gets its own watchpoint log
+    impl Clone for Amplifier {
+        fn clone(&self) -> Amplifier {
+            let mut amp = self.amp.clone();
+            amp.unwatch(self.watch);
+            Amplifier::wrap(amp, self.dirty.clone())
+        }
+    }
+
+    impl Amplifier {
+        pub fn new(input: Vec<isize>) -> Amplifier {
+            Amplifier::wrap(intcode::Amplifier::new(IMAGE.to_vec(), input), vec![0; BLOCKS])
+        }
+
+        fn wrap(mut amp: intcode::Amplifier, dirty: Vec<u32>) -> Amplifier {
+            let written = Arc::new(Mutex::new(Vec::new()));
+            let log = Arc::clone(&written);
+            let watch = amp.watch(Watchpoint::new(0..IMAGE.len(), Access::Write).call(move |hit| {
+                log.lock().unwrap().push((hit.address, hit.old.unwrap_or(hit.new), hit.new));
+                false
+            }));
+            Amplifier { amp, dirty, written, watch }
+        }
+
+        pub fn set_network_mode(&mut self, new_setting: bool) {
+            self.amp.set_network_mode(new_setting);
+        }
+
+        pub fn push_input(&mut self, input: isize) {
+            self.amp.push_input(input);
+        }
+
+        pub fn push_input_vec(&mut self, input: Vec<isize>) {
+            self.amp.push_input_vec(input);
+        }
+
+        pub fn push_input_vec_deque(&mut self, input: VecDeque<isize>) {
+            self.amp.push_input_vec_deque(input);
+        }
+
+        pub fn get_program_clone(&self) -> Vec<isize> {
+            self.amp.get_program_clone()
+        }
+
+        fn prompt_input(&mut self) {
+            let mut input_string = String::new();
+            println!("Please input a number.");
+            io::stdin()
+                .read_line(&mut input_string)
+                .expect("error reading line");
+            if let Ok(num) = input_string.trim().parse() {
+                self.push_input(num);
+            }
+        }
+
+        // memory index for an address, None if the interpreter would fault on it
+        fn index(&self, address: isize) -> Option<usize> {
+            usize::try_from(address).ok().filter(|index| *index < self.amp.memory_size())
+        }
+
+        // returns true if the write changed translated code
+        fn track(&mut self, address: usize, old: isize, value: isize) -> bool {
+            if address >= IMAGE.len() || CELL_BLOCK[address] == u32::MAX || old == value {
+                return false;
+            }
+            let block = CELL_BLOCK[address] as usize;
+            if old == IMAGE[address] {
+                self.dirty[block] += 1;
+            } else if value == IMAGE[address] {
+                self.dirty[block] -= 1;
+            }
+            true
+        }
+
+        fn store(&mut self, address: usize, value: isize) -> bool {
+            let old = std::mem::replace(&mut self.amp.memory_mut()[address], value);
+            self.track(address, old, value)
+        }
+
+        // runs one instruction on the interpreter, Some ends run_program_until_output
+        fn interpret(&mut self, debug: bool) -> Option<Option<isize>> {
+            let event = self.amp.step(debug);
+            let written = std::mem::take(&mut *self.written.lock().unwrap());
+            for (address, old, value) in written {
+                self.track(address, old, value);
+            }
+            match event {
+                Ok(None) | Ok(Some(Event::Watchpoint(_))) => None,
+                Ok(Some(Event::Output(value))) => Some(Some(value)),
+                Ok(Some(Event::NeedInput)) => {
+                    self.prompt_input();
+                    None
+                }
+                Ok(Some(Event::Halted)) => Some(None),
+                Err(fault) => panic!("{}", fault),
+            }
+        }
+
+        pub fn run_program(&mut self, debug: bool) -> Option<isize> {
+            let mut output = None;
+            while let Some(value) = self.run_program_until_output(debug) {
+                output = Some(value);
+            }
+            output
+        }
+
+        // the whole run is a single pass through the image, it stays interpreted
+        pub fn run_program_in_compatibility_mode(&mut self, noun: isize, verb: isize, debug: bool) -> isize {
+            self.amp.run_program_in_compatibility_mode(noun, verb, debug)
+        }
+"#;
+
+struct Translation {
+    blocks: Vec<Vec<Decoded>>,
+    // operand cells the program itself overwrites, they are read from memory
+    patched: BTreeSet<usize>,
+    cell_block: Vec<u32>,
+}
+
+fn is_jump(inst: &Decoded) -> bool {
+    inst.opcode == 5 || inst.opcode == 6
+}
+
+fn ends_block(inst: &Decoded) -> bool {
+    is_jump(inst) || inst.opcode == 4 || inst.opcode == 99
+}
+
+// static addresses have to lie in memory, everything else is checked by the generated code
+fn translatable(inst: &Decoded, patched: &BTreeSet<usize>, size: usize) -> bool {
+    let layout = analysis::params(inst.opcode).unwrap();
+    layout.iter().enumerate().all(|(i, param)| {
+        let dynamic = patched.contains(&(inst.address + 1 + i));
+        let mode = inst.modes[i];
+        let value = inst.params[i];
+        match param {
+            Param::Write if mode == 1 => false,
+            Param::Target if mode == 1 && !dynamic => value >= 0,
+            _ => mode != 0 || dynamic || (value >= 0 && (value as usize) < size),
+        }
+    })
+}
+
+fn split(program: &[isize]) -> Translation {
+    let size = program.len() + intcode::PADDING;
+    let analysis = analysis::analyze(program);
+    let insts: BTreeMap<usize, Decoded> = analysis.reachable.into_iter()
+        .map(|inst| (inst.address, inst))
+        .collect();
+
+    let mut operand_cells = BTreeSet::new();
+    for inst in insts.values() {
+        operand_cells.extend(inst.address + 1..inst.address + inst.width());
+    }
+    let mut patched = BTreeSet::new();
+    for inst in insts.values() {
+        let layout = analysis::params(inst.opcode).unwrap();
+        for (i, param) in layout.iter().enumerate() {
+            let value = inst.params[i];
+            if *param == Param::Write && inst.modes[i] == 0 && value >= 0 && operand_cells.contains(&(value as usize)) {
+                patched.insert(value as usize);
+            }
+        }
+    }
+
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+    for inst in insts.values() {
+        let next = inst.address + inst.width();
+        if ends_block(inst) || !translatable(inst, &patched, size) {
+            leaders.insert(next);
+        }
+        if is_jump(inst) && inst.modes[1] == 1 && inst.params[1] >= 0 && !patched.contains(&(inst.address + 2)) {
+            leaders.insert(inst.params[1] as usize);
+        }
+    }
+
+    let mut blocks = Vec::new();
+    let mut cell_block = vec![NO_BLOCK; program.len()];
+    for leader in leaders.iter() {
+        let mut block = Vec::new();
+        let mut address = *leader;
+        while let Some(inst) = insts.get(&address) {
+            let cells = inst.address..inst.address + inst.width();
+            let overlaps = cells.clone().any(|cell| cell_block[cell] != NO_BLOCK);
+            if (address != *leader && leaders.contains(&address)) || overlaps || !translatable(inst, &patched, size) {
+                break;
+            }
+            for cell in cells.filter(|cell| !patched.contains(cell)) {
+                cell_block[cell] = blocks.len() as u32;
+            }
+            block.push(inst.clone());
+            address += inst.width();
+            if ends_block(inst) {
+                break;
+            }
+        }
+        if !block.is_empty() {
+            blocks.push(block);
+        }
+    }
+    Translation { blocks, patched, cell_block }
+}
+
+impl Translation {
+    // leaves the block before `inst`, the interpreter runs it
+    fn bail(inst: &Decoded) -> String {
+        format!("{{ self.amp.set_ip({}); break 'translated; }}", inst.address)
+    }
+
+    fn checked(inst: &Decoded, value: &str) -> String {
+        format!("match {} {{ Some(value) => value, None => {} }}", value, Translation::bail(inst))
+    }
+
+    fn raw(&self, inst: &Decoded, i: usize) -> String {
+        let cell = inst.address + 1 + i;
+        if self.patched.contains(&cell) {
+            format!("self.amp.memory()[{}]", cell)
+        } else {
+            format!("({})", inst.params[i])
+        }
+    }
+
+    fn dynamic(&self, inst: &Decoded, i: usize) -> bool {
+        inst.modes[i] == 2 || self.patched.contains(&(inst.address + 1 + i))
+    }
+
+    // index of a position or relative parameter, dynamic ones are checked into `a<i>` first
+    fn address(&self, out: &mut String, inst: &Decoded, i: usize) -> String {
+        let raw = self.raw(inst, i);
+        let index = match inst.modes[i] {
+            0 if self.dynamic(inst, i) => format!("self.index({})", raw),
+            0 => return inst.params[i].to_string(),
+            _ => format!("self.amp.relative_base().checked_add({}).and_then(|a| self.index(a))", raw),
+        };
+        writeln!(out, "                    let a{} = {};", i, Translation::checked(inst, &index)).unwrap();
+        format!("a{}", i)
+    }
+
+    fn load(&self, out: &mut String, inst: &Decoded, i: usize) -> String {
+        match inst.modes[i] {
+            1 => self.raw(inst, i),
+            _ => format!("self.amp.memory()[{}]", self.address(out, inst, i)),
+        }
+    }
+
+    fn store(&self, out: &mut String, inst: &Decoded, i: usize, value: &str) {
+        let next = inst.address + inst.width();
+        let static_address = inst.modes[i] == 0 && !self.dynamic(inst, i);
+        let address = self.address(out, inst, i);
+        if static_address && self.cell_block.get(inst.params[i] as usize).is_none_or(|b| *b == NO_BLOCK) {
+            writeln!(out, "                    self.amp.memory_mut()[{}] = {};", address, value).unwrap();
+        } else {
+            writeln!(out, "                    if self.store({}, {}) {{ self.amp.set_ip({}); continue 'run; }}",
+                     address, value, next).unwrap();
+        }
+    }
+
+    fn statement(&self, out: &mut String, inst: &Decoded) {
+        let next = inst.address + inst.width();
+        match inst.opcode {
+            1 | 2 | 7 | 8 => {
+                let (a, b) = (self.load(out, inst, 0), self.load(out, inst, 1));
+                let value = match inst.opcode {
+                    1 => Translation::checked(inst, &format!("isize::checked_add({}, {})", a, b)),
+                    2 => Translation::checked(inst, &format!("isize::checked_mul({}, {})", a, b)),
+                    7 => format!("if {} < {} {{ 1 }} else {{ 0 }}", a, b),
+                    _ => format!("if {} == {} {{ 1 }} else {{ 0 }}", a, b),
+                };
+                writeln!(out, "                    let value: isize = {};", value).unwrap();
+                self.store(out, inst, 2, "value");
+            }
+            4 => {
+                let value = self.load(out, inst, 0);
+                writeln!(out, "                    let value = {};", value).unwrap();
+                writeln!(out, "                    self.amp.set_ip({});", next).unwrap();
+                writeln!(out, "                    return Some(value);").unwrap();
+            }
+            5 | 6 => {
+                let comparison = if inst.opcode == 5 { "!=" } else { "==" };
+                let condition = self.load(out, inst, 0);
+                let target = match inst.modes[1] {
+                    1 if !self.dynamic(inst, 1) => inst.params[1].to_string(),
+                    _ => {
+                        let target = self.load(out, inst, 1);
+                        Translation::checked(inst, &format!("usize::try_from({}).ok()", target))
+                    }
+                };
+                writeln!(out, "                    if {} {} 0 {{", condition, comparison).unwrap();
+                writeln!(out, "                        let target = {};", target).unwrap();
+                writeln!(out, "                        self.amp.set_ip(target);").unwrap();
+                writeln!(out, "                    }} else {{").unwrap();
+                writeln!(out, "                        self.amp.set_ip({});", next).unwrap();
+                writeln!(out, "                    }}").unwrap();
+                writeln!(out, "                    continue 'run;").unwrap();
+            }
+            9 => {
+                let offset = self.load(out, inst, 0);
+                let rb = Translation::checked(inst, &format!("self.amp.relative_base().checked_add({})", offset));
+                writeln!(out, "                    let rb = {};", rb).unwrap();
+                writeln!(out, "                    self.amp.set_relative_base(rb);").unwrap();
+            }
+            // input and halt
+            _ => writeln!(out, "                    {}", Translation::bail(inst)).unwrap(),
+        }
+    }
+}
+
+// Turns an intcode image into a Rust module `name` with an `Amplifier` that runs the reachable
+// code as straight-line Rust, dispatching on ip per basic block. The crate that includes the
+// module needs intcomputer as a dependency.
+pub fn translate(program: &[isize], name: &str) -> String {
+    let translation = split(program);
+    let mut out = String::new();
+    writeln!(out, "#[allow(clippy::all, unused)]").unwrap();
+    writeln!(out, "pub mod {} {{", name).unwrap();
+    writeln!(out, "    static IMAGE: [isize; {}] = {:?};", program.len(), program).unwrap();
+    writeln!(out, "    static CELL_BLOCK: [u32; {}] = {:?};", program.len(), translation.cell_block).unwrap();
+    writeln!(out, "    const BLOCKS: usize = {};", translation.blocks.len()).unwrap();
+    out.push_str(RUNTIME);
+    writeln!(out).unwrap();
+    writeln!(out, "        pub fn run_program_until_output(&mut self, debug: bool) -> Option<isize> {{").unwrap();
+    writeln!(out, "            'run: loop {{").unwrap();
+    writeln!(out, "                if self.amp.ip() >= self.amp.memory_size() {{").unwrap();
+    writeln!(out, "                    return None;").unwrap();
+    writeln!(out, "                }}").unwrap();
+    writeln!(out, "                'translated: {{").unwrap();
+    writeln!(out, "                match self.amp.ip() {{").unwrap();
+    for (index, block) in translation.blocks.iter().enumerate() {
+        writeln!(out, "                {} if self.dirty[{}] == 0 => {{", block[0].address, index).unwrap();
+        for inst in block.iter() {
+            translation.statement(&mut out, inst);
+        }
+        let last = block.last().unwrap();
+        if !ends_block(last) {
+            writeln!(out, "                    self.amp.set_ip({});", last.address + last.width()).unwrap();
+            writeln!(out, "                    continue 'run;").unwrap();
+        }
+        writeln!(out, "                }}").unwrap();
+    }
+    writeln!(out, "                _ => (),").unwrap();
+    writeln!(out, "                }}").unwrap();
+    writeln!(out, "                }}").unwrap();
+    writeln!(out, "                if let Some(output) = self.interpret(debug) {{").unwrap();
+    writeln!(out, "                    return output;").unwrap();
+    writeln!(out, "                }}").unwrap();
+    writeln!(out, "            }}").unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+// For build scripts: translates `program_file` into `$OUT_DIR/<name>.rs`, which
+// the crate then pulls in with `include!(concat!(env!("OUT_DIR"), "/<name>.rs"));`.
+pub fn build(program_file: &str, name: &str) {
+    println!("cargo:rerun-if-changed={}", program_file);
+    let program = intcode::read_data(program_file);
+    let out_dir = env::var("OUT_DIR").expect("translate::build has to be called from a build script");
+    let path = Path::new(&out_dir).join(format!("{}.rs", name));
+    fs::write(&path, translate(&program, name)).expect("could not write the translated program");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::translate::{split, translate, NO_BLOCK};
+
+    fn starts(program: &[isize]) -> Vec<usize> {
+        split(program).blocks.iter().map(|block| block[0].address).collect()
+    }
+
+    #[test]
+    fn blocks_split_at_jumps_and_outputs() {
+        // 0: in [13]; 2: jf [13], 10; 5: out [13]; 7: jt 1, 0; 10: halt
+        let program = vec![3, 13, 1006, 13, 10, 4, 13, 1105, 1, 0, 99, 0, 0, 0];
+        assert_eq!(vec![0, 5, 7, 10], starts(&program));
+    }
+
+    #[test]
+    fn patched_operands_are_read_from_memory() {
+        // 0: add 1, 0 -> [5], which is the operand of 4: out [0]
+        let program = vec![1101, 1, 0, 5, 4, 0, 99];
+        let translation = split(&program);
+        assert!(translation.patched.contains(&5));
+        assert_eq!(NO_BLOCK, translation.cell_block[5]);
+        assert_ne!(NO_BLOCK, translation.cell_block[4]);
+        assert!(translate(&program, "patched").contains("self.index(self.amp.memory()[5])"));
+    }
+
+    #[test]
+    fn immediate_writes_are_interpreted() {
+        let program = vec![1101, 1, 1, 5, 11101, 1, 1, 3, 99];
+        assert_eq!(vec![0, 8], starts(&program));
+    }
+
+    #[test]
+    fn faulting_operands_are_interpreted() {
+        // 0: rb += -5; 2: [rb + 0] = 1 + 1, a negative address
+        let code = translate(&[109, -5, 21101, 1, 1, 0, 99], "faulting");
+        assert!(code.contains("let a2 = match self.amp.relative_base().checked_add((0)).and_then(|a| self.index(a)) \
+                               { Some(value) => value, None => { self.amp.set_ip(2); break 'translated; } };"));
+        assert!(code.contains("self.amp.step(debug)"));
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+    // a write that stores a different value than the cell held before
+    Change,
+}
+
+// What triggered a watchpoint. `old` is None for reads and for mapped devices.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hit {
+    pub id: usize,
+    pub ip: usize,
+    pub address: usize,
+    pub access: Access,
+    pub old: Option<isize>,
+    pub new: isize,
+}
+
+pub type Predicate = Arc<dyn Fn(isize) -> bool + Send + Sync>;
+// returning true pauses the vm
+pub type Callback = Arc<Mutex<dyn FnMut(&Hit) -> bool + Send>>;
+
+#[derive(Clone)]
+pub enum Action {
+    Pause,
+    Call(Callback),
+}
+
+// Instruction reads and writes of memory are watched, fetching instructions is not.
+#[derive(Clone)]
+pub struct Watchpoint {
+    pub range: Range<usize>,
+    pub access: Access,
+    pub predicate: Option<Predicate>,
+    pub action: Action,
+}
+
+impl Watchpoint {
+    pub fn new(range: Range<usize>, access: Access) -> Watchpoint {
+        Watchpoint { range, access, predicate: None, action: Action::Pause }
+    }
+
+    pub fn when<F: Fn(isize) -> bool + Send + Sync + 'static>(mut self, predicate: F) -> Watchpoint {
+        self.predicate = Some(Arc::new(predicate));
+        self
+    }
+
+    pub fn call<F: FnMut(&Hit) -> bool + Send + 'static>(mut self, callback: F) -> Watchpoint {
+        self.action = Action::Call(Arc::new(Mutex::new(callback)));
+        self
+    }
+
+    fn matches(&self, address: usize, access: Access, old: Option<isize>, new: isize) -> bool {
+        let access_matches = match self.access {
+            Access::Change => access == Access::Write && old.is_some_and(|old| old != new),
+            watched => watched == access,
+        };
+        access_matches && self.range.contains(&address) && self.predicate.as_ref().is_none_or(|p| p(new))
+    }
+}
+
+impl fmt::Debug for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Watchpoint")
+            .field("range", &self.range)
+            .field("access", &self.access)
+            .field("predicate", &self.predicate.is_some())
+            .field("pause", &matches!(self.action, Action::Pause))
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Watchpoints {
+    next_id: usize,
+    active: Vec<(usize, Watchpoint)>,
+}
+
+impl Watchpoints {
+    pub fn add(&mut self, watchpoint: Watchpoint) -> usize {
+        self.next_id += 1;
+        self.active.push((self.next_id, watchpoint));
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: usize) -> bool {
+        let len = self.active.len();
+        self.active.retain(|(other, _)| *other != id);
+        self.active.len() != len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    // runs the callbacks of all matching watchpoints and returns the first hit that pauses
+    pub fn check(&self, ip: usize, address: usize, access: Access, old: Option<isize>, new: isize) -> Option<Hit> {
+        let mut pause = None;
+        for (id, watchpoint) in self.active.iter() {
+            if !watchpoint.matches(address, access, old, new) {
+                continue;
+            }
+            let hit = Hit { id: *id, ip, address, access: watchpoint.access, old, new };
+            let pauses = match &watchpoint.action {
+                Action::Pause => true,
+                Action::Call(callback) => (callback.lock().unwrap())(&hit),
+            };
+            if pauses && pause.is_none() {
+                pause = Some(hit);
+            }
+        }
+        pause
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intcode::{Amplifier, Event};
+    use crate::watch::{Access, Hit, Watchpoint};
+    use std::sync::{Arc, Mutex};
+
+    // counts [20] up to 5 and outputs every value, [21] holds [20] < 5
+    fn counter() -> Amplifier {
+        Amplifier::new(vec![1001, 20, 1, 20, 4, 20, 1007, 20, 5, 21, 1005, 21, 0, 99], vec![])
+    }
+
+    #[test]
+    fn pause_on_write_with_predicate() {
+        let mut amp = counter();
+        let id = amp.watch(Watchpoint::new(20..21, Access::Write).when(|value| value == 3));
+        assert_eq!(Ok(Event::Output(1)), amp.run_until_event(false));
+        assert_eq!(Ok(Event::Output(2)), amp.run_until_event(false));
+        let hit = Hit { id, ip: 0, address: 20, access: Access::Write, old: Some(2), new: 3 };
+        assert_eq!(Ok(Event::Watchpoint(hit)), amp.run_until_event(false));
+        assert_eq!(4, amp.ip());
+        assert_eq!(Ok(Event::Output(3)), amp.run_until_event(false));
+        assert!(amp.unwatch(id));
+        assert!(!amp.unwatch(id));
+    }
+
+    #[test]
+    fn output_is_not_lost() {
+        // the read by the output instruction pauses after the output has been reported
+        let mut amp = counter();
+        amp.watch(Watchpoint::new(20..22, Access::Read).when(|value| value == 1));
+        assert_eq!(Ok(Event::Output(1)), amp.run_until_event(false));
+        assert!(matches!(amp.run_until_event(false), Ok(Event::Watchpoint(Hit { ip: 4, address: 20, .. }))));
+        assert!(matches!(amp.run_until_event(false), Ok(Event::Watchpoint(Hit { ip: 6, address: 20, .. }))));
+        // the comparison [21] = [20] < 5 is true, which is read back by the jump
+        assert!(matches!(amp.run_until_event(false), Ok(Event::Watchpoint(Hit { ip: 10, address: 21, .. }))));
+        assert!(matches!(amp.run_until_event(false), Ok(Event::Watchpoint(Hit { ip: 0, address: 20, .. }))));
+        assert_eq!(Ok(Event::Output(2)), amp.run_until_event(false));
+    }
+
+    #[test]
+    fn callbacks_in_plain_runs() {
+        // the comparison result only changes twice: 0 -> 1 and 1 -> 0 at the end
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let seen = changes.clone();
+        let mut amp = counter();
+        amp.watch(Watchpoint::new(21..22, Access::Change).call(move |hit| {
+            seen.lock().unwrap().push((hit.old, hit.new));
+            true
+        }));
+        assert_eq!(Some(5), amp.run_program(false));
+        assert_eq!(vec![(Some(0), 1), (Some(1), 0)], *changes.lock().unwrap());
+    }
+
+    #[test]
+    fn immediate_operands_are_fetched() {
+        // the increment at [2] and the bound at [8] are immediates, [20] is read by position
+        let reads = Arc::new(Mutex::new(Vec::new()));
+        let seen = reads.clone();
+        let mut amp = counter();
+        amp.watch(Watchpoint::new(0..21, Access::Read).call(move |hit| {
+            seen.lock().unwrap().push(hit.address);
+            false
+        }));
+        assert_eq!(Some(5), amp.run_program(false));
+        assert!(reads.lock().unwrap().iter().all(|&address| address == 20));
+        assert_eq!(15, reads.lock().unwrap().len());
+    }
+}
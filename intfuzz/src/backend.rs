@@ -0,0 +1,192 @@
+use crate::generate::{Case, Features};
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+thread_local! {
+    // set while a backend runs on this thread, its panics are faults and not worth printing
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+// Wraps the current panic hook once, panics on other threads still reach it.
+fn quiet_backend_panics() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !QUIET.with(|quiet| quiet.get()) {
+                previous(info);
+            }
+        }));
+    });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub outputs: Vec<isize>,
+    // only the cells of the loaded image, padding is cut off
+    pub memory: Vec<isize>,
+    pub fault: Option<String>,
+}
+
+pub trait Backend {
+    fn name(&self) -> &'static str;
+    fn supports(&self, features: &Features) -> bool;
+    // returns the outputs and the final memory, panics count as faults
+    fn execute(&self, program: Vec<isize>, inputs: Vec<isize>) -> (Vec<isize>, Vec<isize>);
+
+    fn run(&self, case: &Case) -> Outcome {
+        let program = case.program();
+        let len = program.len();
+        let inputs = case.inputs.clone();
+        quiet_backend_panics();
+        QUIET.with(|quiet| quiet.set(true));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.execute(program, inputs)));
+        QUIET.with(|quiet| quiet.set(false));
+        match result {
+            Ok((outputs, mut memory)) => {
+                memory.truncate(len);
+                Outcome { outputs, memory, fault: None }
+            }
+            Err(payload) => {
+                let message = if let Some(s) = payload.downcast_ref::<&str>() {
+                    s.to_string()
+                } else if let Some(s) = payload.downcast_ref::<String>() {
+                    s.clone()
+                } else {
+                    "panic".to_string()
+                };
+                Outcome { outputs: Vec::new(), memory: Vec::new(), fault: Some(message) }
+            }
+        }
+    }
+}
+
+pub struct Intcomputer;
+
+impl Backend for Intcomputer {
+    fn name(&self) -> &'static str {
+        "intcomputer"
+    }
+
+    fn supports(&self, _features: &Features) -> bool {
+        true
+    }
+
+    fn execute(&self, program: Vec<isize>, inputs: Vec<isize>) -> (Vec<isize>, Vec<isize>) {
+        let mut amp = intcomputer::intcode::Amplifier::new_test(program, inputs);
+        let mut outputs = Vec::new();
+        while let Some(output) = amp.run_program_until_output(false) {
+            outputs.push(output);
+        }
+        (outputs, amp.get_program_clone())
+    }
+}
+
+pub struct Nic;
+
+impl Backend for Nic {
+    fn name(&self) -> &'static str {
+        "nic"
+    }
+
+    fn supports(&self, _features: &Features) -> bool {
+        true
+    }
+
+    fn execute(&self, program: Vec<isize>, inputs: Vec<isize>) -> (Vec<isize>, Vec<isize>) {
+        let mut amp = nic::intcode::NetworkAmplifier::new_test(program);
+        // the nic reads packets, an odd input is padded with the empty queue marker
+        for pair in inputs.chunks(2) {
+            amp.push_input((pair[0], if pair.len() == 2 { pair[1] } else { -1 }));
+        }
+        let mut outputs = Vec::new();
+        while let Some(output) = amp.run_program_until_output(false) {
+            outputs.push(output);
+        }
+        (outputs, amp.get_program_clone())
+    }
+}
+
+pub struct Day9;
+
+impl Backend for Day9 {
+    fn name(&self) -> &'static str {
+        "9Dec"
+    }
+
+    fn supports(&self, _features: &Features) -> bool {
+        true
+    }
+
+    fn execute(&self, program: Vec<isize>, inputs: Vec<isize>) -> (Vec<isize>, Vec<isize>) {
+        let mut amp = _9::Amplifier::new(program, inputs);
+        amp.run_program(false);
+        (amp.outputs.clone(), amp.get_program_clone())
+    }
+}
+
+pub struct Day7;
+
+impl Backend for Day7 {
+    fn name(&self) -> &'static str {
+        "7Dec"
+    }
+
+    fn supports(&self, features: &Features) -> bool {
+        !features.relative
+    }
+
+    fn execute(&self, program: Vec<isize>, inputs: Vec<isize>) -> (Vec<isize>, Vec<isize>) {
+        let mut amp = _7::Amplifier::new(program, inputs);
+        let mut outputs = Vec::new();
+        while let Some(output) = amp.run_program(false) {
+            outputs.push(output);
+        }
+        (outputs, amp.get_program_clone())
+    }
+}
+
+pub struct Day5;
+
+impl Backend for Day5 {
+    fn name(&self) -> &'static str {
+        "5Dec"
+    }
+
+    fn supports(&self, features: &Features) -> bool {
+        !features.relative
+    }
+
+    fn execute(&self, program: Vec<isize>, inputs: Vec<isize>) -> (Vec<isize>, Vec<isize>) {
+        let mut inputs = inputs.into_iter();
+        let mut outputs = Vec::new();
+        let memory = _5::run_program_with_io(program,
+            || inputs.next().expect("out of input"),
+            |output| outputs.push(output as isize));
+        (outputs, memory)
+    }
+}
+
+pub struct Day2;
+
+impl Backend for Day2 {
+    fn name(&self) -> &'static str {
+        "2Dec"
+    }
+
+    fn supports(&self, features: &Features) -> bool {
+        Features::day2().includes(features)
+    }
+
+    fn execute(&self, program: Vec<isize>, _inputs: Vec<isize>) -> (Vec<isize>, Vec<isize>) {
+        let program: Vec<usize> = program.into_iter().map(|v| v as usize).collect();
+        let (noun, verb) = (program[1], program[2]);
+        let memory = _2::execute(program, noun, verb, false);
+        (Vec::new(), memory.into_iter().map(|v| v as isize).collect())
+    }
+}
+
+pub fn alternatives() -> Vec<Box<dyn Backend>> {
+    vec![Box::new(Nic), Box::new(Day9), Box::new(Day7), Box::new(Day5), Box::new(Day2)]
+}
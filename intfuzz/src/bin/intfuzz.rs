@@ -0,0 +1,40 @@
+use intfuzz::generate::Features;
+use std::env;
+use std::process;
+
+fn usage() -> ! {
+    eprintln!("usage: intfuzz [day2|day5|day9|all] [iterations] [seed]");
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let features = match args.first().map(|s| s.as_str()) {
+        Some("day2") => Features::day2(),
+        Some("day5") => Features::day5(),
+        Some("day9") | None => Features::day9(),
+        Some("all") => Features::all(),
+        Some(_) => usage(),
+    };
+    let iterations = match args.get(1).map(|s| s.parse()) {
+        Some(Ok(n)) => n,
+        Some(Err(_)) => usage(),
+        None => 1000,
+    };
+    let seed = match args.get(2).map(|s| s.parse()) {
+        Some(Ok(n)) => n,
+        Some(Err(_)) => usage(),
+        None => 0,
+    };
+
+    let findings = intfuzz::fuzz(seed, iterations, 12, &features);
+    for finding in findings.iter() {
+        println!("{} (seed {}): {}", finding.backend, finding.seed, finding.difference);
+        println!("{}", finding.reproducer);
+        println!();
+    }
+    println!("{} programs, {} backends differ from the reference", iterations, findings.len());
+    if !findings.is_empty() {
+        process::exit(1);
+    }
+}
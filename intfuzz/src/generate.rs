@@ -0,0 +1,281 @@
+use std::fmt;
+
+// xorshift64*, good enough for generating test programs and reproducible from a seed
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn range(&mut self, lo: isize, hi: isize) -> isize {
+        lo + self.below((hi - lo) as usize) as isize
+    }
+
+    pub fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+}
+
+// Which parts of the instruction set a generated program may use. Backends
+// only get programs whose features they claim to support.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Features {
+    pub io: bool,
+    pub jumps: bool,
+    pub compare: bool,
+    pub immediate: bool,
+    pub relative: bool,
+    pub negative: bool,
+    pub immediate_writes: bool,
+}
+
+impl Features {
+    pub fn day2() -> Features {
+        Features {
+            io: false,
+            jumps: false,
+            compare: false,
+            immediate: false,
+            relative: false,
+            negative: false,
+            immediate_writes: false,
+        }
+    }
+
+    pub fn day5() -> Features {
+        Features {
+            io: true,
+            jumps: true,
+            compare: true,
+            immediate: true,
+            ..Features::day2()
+        }
+    }
+
+    pub fn day9() -> Features {
+        Features {
+            relative: true,
+            negative: true,
+            ..Features::day5()
+        }
+    }
+
+    pub fn all() -> Features {
+        Features {
+            immediate_writes: true,
+            ..Features::day9()
+        }
+    }
+
+    pub fn includes(&self, other: &Features) -> bool {
+        (self.io || !other.io) &&
+        (self.jumps || !other.jumps) &&
+        (self.compare || !other.compare) &&
+        (self.immediate || !other.immediate) &&
+        (self.relative || !other.relative) &&
+        (self.negative || !other.negative) &&
+        (self.immediate_writes || !other.immediate_writes)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Operand {
+    Imm(isize),
+    // cell k of the data region, position mode
+    Data(usize),
+    // cell k of the data region, relative mode (rb points at the data region)
+    Rel(usize),
+    // index of an instruction, immediate mode; one past the last is the HALT
+    Target(usize),
+    // address of the data region, immediate mode
+    DataBase,
+}
+
+impl Operand {
+    fn mode(&self) -> isize {
+        match self {
+            Operand::Data(_) => 0,
+            Operand::Rel(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inst {
+    pub opcode: isize,
+    pub operands: Vec<Operand>,
+}
+
+impl Inst {
+    pub fn width(&self) -> usize {
+        1 + self.operands.len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub code: Vec<Inst>,
+    pub data: Vec<isize>,
+    pub inputs: Vec<isize>,
+}
+
+impl Case {
+    pub fn program(&self) -> Vec<isize> {
+        let mut addresses = Vec::new();
+        let mut address = 0;
+        for inst in self.code.iter() {
+            addresses.push(address);
+            address += inst.width();
+        }
+        // the HALT
+        addresses.push(address);
+        let data_start = address + 1;
+
+        let mut program = Vec::new();
+        for inst in self.code.iter() {
+            let mut word = inst.opcode;
+            let mut factor = 100;
+            for operand in inst.operands.iter() {
+                word += operand.mode() * factor;
+                factor *= 10;
+            }
+            program.push(word);
+            for operand in inst.operands.iter() {
+                program.push(match *operand {
+                    Operand::Imm(value) => value,
+                    Operand::Data(k) => (data_start + k) as isize,
+                    Operand::Rel(k) => k as isize,
+                    Operand::Target(i) => addresses[i] as isize,
+                    Operand::DataBase => data_start as isize,
+                });
+            }
+        }
+        program.push(99);
+        program.extend(self.data.iter());
+        program
+    }
+
+    pub fn reads(&self) -> usize {
+        self.code.iter().filter(|inst| inst.opcode == 3).count()
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |values: &[isize]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+        writeln!(f, "program: {}", join(&self.program()))?;
+        write!(f, "inputs:  {}", join(&self.inputs))
+    }
+}
+
+const DATA_LEN: usize = 8;
+
+fn value(rng: &mut Rng, features: &Features) -> isize {
+    if features.negative {
+        rng.range(-9, 10)
+    } else {
+        rng.range(0, 10)
+    }
+}
+
+fn read_operand(rng: &mut Rng, features: &Features) -> Operand {
+    match rng.below(3) {
+        0 if features.immediate => Operand::Imm(value(rng, features)),
+        1 if features.relative => Operand::Rel(rng.below(DATA_LEN)),
+        _ => Operand::Data(rng.below(DATA_LEN)),
+    }
+}
+
+fn write_operand(rng: &mut Rng, features: &Features) -> Operand {
+    if features.immediate_writes && rng.chance(10) {
+        Operand::Imm(value(rng, features))
+    } else if features.relative && rng.chance(50) {
+        Operand::Rel(rng.below(DATA_LEN))
+    } else {
+        Operand::Data(rng.below(DATA_LEN))
+    }
+}
+
+// Jumps only go forward and writes only go to the data region, so every
+// generated program terminates.
+pub fn generate(rng: &mut Rng, features: &Features, max_len: usize) -> Case {
+    let mut opcodes = vec![1, 2];
+    if features.io {
+        opcodes.extend(&[3, 4]);
+    }
+    if features.jumps {
+        opcodes.extend(&[5, 6]);
+    }
+    if features.compare {
+        opcodes.extend(&[7, 8]);
+    }
+
+    let mut code = Vec::new();
+    if features.relative {
+        code.push(Inst { opcode: 9, operands: vec![Operand::DataBase] });
+    }
+    let total = code.len() + 1 + rng.below(max_len.max(1));
+    while code.len() < total {
+        let index = code.len();
+        let opcode = opcodes[rng.below(opcodes.len())];
+        let operands = match opcode {
+            1 | 2 | 7 | 8 => vec![read_operand(rng, features), read_operand(rng, features), write_operand(rng, features)],
+            3 => vec![write_operand(rng, features)],
+            4 => vec![read_operand(rng, features)],
+            _ => vec![read_operand(rng, features), Operand::Target(index + 1 + rng.below(total - index))],
+        };
+        code.push(Inst { opcode, operands });
+    }
+
+    let data = (0..DATA_LEN).map(|_| value(rng, features)).collect();
+    let mut case = Case { code, data, inputs: Vec::new() };
+    case.inputs = (0..case.reads()).map(|_| value(rng, features)).collect();
+    case
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{generate, Features, Rng};
+    use intcomputer::analysis::{analyze, Kind};
+
+    #[test]
+    fn generated_programs_are_well_formed() {
+        let mut rng = Rng::new(7);
+        for _ in 0..500 {
+            let case = generate(&mut rng, &Features::day9(), 16);
+            let program = case.program();
+            let analysis = analyze(&program);
+            assert_eq!(Vec::<Kind>::new(),
+                       analysis.diagnostics.into_iter().map(|d| d.kind).collect::<Vec<_>>(),
+                       "{}", case);
+            assert_eq!(case.reads(), case.inputs.len());
+        }
+    }
+
+    #[test]
+    fn day2_programs_use_plain_opcodes() {
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            let program = generate(&mut rng, &Features::day2(), 16).program();
+            let mut ip = 0;
+            while program[ip] != 99 {
+                assert!(program[ip] == 1 || program[ip] == 2);
+                assert!(program[ip + 1..ip + 4].iter().all(|v| *v >= 0));
+                ip += 4;
+            }
+        }
+    }
+}
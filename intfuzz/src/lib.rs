@@ -0,0 +1,196 @@
+pub mod backend;
+pub mod generate;
+
+use backend::{Backend, Intcomputer, Outcome};
+use generate::{Case, Features, Operand, Rng};
+
+// Describes how `actual` deviates from the reference outcome, if it does.
+// When both fault the fault messages are not compared, every VM words them differently.
+pub fn difference(expected: &Outcome, actual: &Outcome) -> Option<String> {
+    match (&expected.fault, &actual.fault) {
+        (None, Some(fault)) => Some(format!("faults: {}", fault)),
+        (Some(fault), None) => Some(format!("does not fault, reference faults: {}", fault)),
+        (Some(_), Some(_)) => None,
+        (None, None) => {
+            if expected.outputs != actual.outputs {
+                return Some(format!("outputs {:?}, reference outputs {:?}", actual.outputs, expected.outputs));
+            }
+            let differing = expected.memory.iter().zip(actual.memory.iter())
+                .position(|(e, a)| e != a);
+            if let Some(address) = differing {
+                return Some(format!("memory[{}] = {}, reference has {}",
+                                    address, actual.memory[address], expected.memory[address]));
+            }
+            None
+        }
+    }
+}
+
+fn diverges(backend: &dyn Backend, case: &Case) -> bool {
+    difference(&Intcomputer.run(case), &backend.run(case)).is_some()
+}
+
+fn remove_instruction(case: &Case, index: usize) -> Case {
+    let mut case = case.clone();
+    case.code.remove(index);
+    for inst in case.code.iter_mut() {
+        for operand in inst.operands.iter_mut() {
+            if let Operand::Target(target) = operand {
+                if *target > index {
+                    *target -= 1;
+                }
+            }
+        }
+    }
+    case.inputs.truncate(case.reads());
+    case
+}
+
+fn simpler_operand(operand: Operand) -> Option<Operand> {
+    match operand {
+        Operand::Imm(v) if v != 0 => Some(Operand::Imm(if v.abs() > 1 { v / 2 } else { 0 })),
+        Operand::Data(k) if k != 0 => Some(Operand::Data(0)),
+        Operand::Rel(k) if k != 0 => Some(Operand::Rel(0)),
+        _ => None,
+    }
+}
+
+// Greedy shrinking: drop instructions, then move operands, data and inputs
+// towards zero, as long as the case still shows the difference.
+pub fn minimize<F: Fn(&Case) -> bool>(case: &Case, interesting: F) -> Case {
+    let mut best = case.clone();
+    let mut progress = true;
+    while progress {
+        progress = false;
+
+        let mut index = best.code.len();
+        while index > 0 {
+            index -= 1;
+            // the leading rb setup keeps relative writes inside the data region
+            if best.code[index].operands == [Operand::DataBase] {
+                continue;
+            }
+            let candidate = remove_instruction(&best, index);
+            if interesting(&candidate) {
+                best = candidate;
+                progress = true;
+            }
+        }
+
+        for i in 0..best.code.len() {
+            for j in 0..best.code[i].operands.len() {
+                while let Some(operand) = simpler_operand(best.code[i].operands[j]) {
+                    let mut candidate = best.clone();
+                    candidate.code[i].operands[j] = operand;
+                    if !interesting(&candidate) {
+                        break;
+                    }
+                    best = candidate;
+                    progress = true;
+                }
+            }
+        }
+
+        for i in 0..best.data.len() + best.inputs.len() {
+            let mut candidate = best.clone();
+            let value = if i < best.data.len() {
+                &mut candidate.data[i]
+            } else {
+                &mut candidate.inputs[i - best.data.len()]
+            };
+            if *value == 0 {
+                continue;
+            }
+            *value = 0;
+            if interesting(&candidate) {
+                best = candidate;
+                progress = true;
+            }
+        }
+    }
+    best
+}
+
+pub struct Finding {
+    pub backend: &'static str,
+    pub seed: u64,
+    pub difference: String,
+    pub reproducer: Case,
+}
+
+// Runs `iterations` random programs on the reference and every backend that
+// supports `features`. Reports at most one minimized finding per backend.
+pub fn fuzz(seed: u64, iterations: usize, max_len: usize, features: &Features) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    let backends: Vec<_> = backend::alternatives().into_iter()
+        .filter(|backend| backend.supports(features))
+        .collect();
+    for iteration in 0..iterations {
+        let case_seed = seed.wrapping_add(iteration as u64);
+        let case = generate::generate(&mut Rng::new(case_seed), features, max_len);
+        let expected = Intcomputer.run(&case);
+        for backend in backends.iter() {
+            if findings.iter().any(|f| f.backend == backend.name()) {
+                continue;
+            }
+            if difference(&expected, &backend.run(&case)).is_none() {
+                continue;
+            }
+            let reproducer = minimize(&case, |c| diverges(backend.as_ref(), c));
+            let difference = difference(&Intcomputer.run(&reproducer), &backend.run(&reproducer)).unwrap();
+            findings.push(Finding { backend: backend.name(), seed: case_seed, difference, reproducer });
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{Backend, Day2, Day5, Day7, Day9, Intcomputer, Nic};
+    use crate::generate::{generate, Features, Rng};
+    use crate::{difference, fuzz};
+
+    #[test]
+    fn day5_program_agrees_everywhere() {
+        // day 5 example: outputs 999, 1000 or 1001 depending on how the input compares to 8
+        let program = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+        let program: Vec<isize> = program.split(',').map(|v| v.parse().unwrap()).collect();
+        for input in 7..10 {
+            let expected = Intcomputer.execute(program.clone(), vec![input]);
+            assert_eq!(vec![992 + input], expected.0);
+            for backend in [&Nic as &dyn Backend, &Day9, &Day7, &Day5].iter() {
+                assert_eq!(expected.0, backend.execute(program.clone(), vec![input]).0, "{}", backend.name());
+            }
+        }
+    }
+
+    #[test]
+    fn day2_profile_agrees() {
+        let mut rng = Rng::new(11);
+        for _ in 0..200 {
+            let case = generate(&mut rng, &Features::day2(), 12);
+            assert_eq!(None, difference(&Intcomputer.run(&case), &Day2.run(&case)), "{}", case);
+        }
+    }
+
+    #[test]
+    fn day9_profile_agrees_with_nic_and_day9() {
+        let mut rng = Rng::new(5);
+        for _ in 0..200 {
+            let case = generate(&mut rng, &Features::day9(), 12);
+            let expected = Intcomputer.run(&case);
+            assert_eq!(None, difference(&expected, &Nic.run(&case)), "{}", case);
+            assert_eq!(None, difference(&expected, &Day9.run(&case)), "{}", case);
+        }
+    }
+
+    #[test]
+    fn immediate_writes_are_minimized() {
+        let features = Features { relative: false, ..Features::all() };
+        let findings = fuzz(1, 200, 12, &features);
+        let day7 = findings.iter().find(|f| f.backend == "7Dec").expect("no finding for 7Dec");
+        // a single immediate mode write is enough: the reference refuses it, 7Dec writes into the instruction
+        assert_eq!(1, day7.reproducer.code.len(), "{}", day7.reproducer);
+        assert!(day7.difference.starts_with("does not fault"));
+    }
+}
@@ -0,0 +1,89 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use intcomputer::intcode;
+use intjit::{Amplifier, Backend};
+
+const BACKENDS: [Backend; 2] = [Backend::Interpreter, Backend::Jit];
+
+fn name(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Interpreter => "interpreter",
+        Backend::Jit => "jit",
+    }
+}
+
+// day 9 part 2, the longest running program in the tree
+fn day9(c: &mut Criterion) {
+    let program = intcode::read_data("../intcomputer/9");
+    let mut group = c.benchmark_group("day9");
+    for backend in BACKENDS.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(name(*backend)), backend, |b, backend| {
+            b.iter(|| Amplifier::with_backend(program.clone(), vec![2], *backend).run_program(false))
+        });
+    }
+    group.finish();
+}
+
+// drawing the initial screen of the arcade cabinet
+fn day13(c: &mut Criterion) {
+    let program = intcode::read_data("../13Dec/program");
+    let mut group = c.benchmark_group("day13");
+    for backend in BACKENDS.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(name(*backend)), backend, |b, backend| {
+            b.iter(|| {
+                let mut amp = Amplifier::with_backend(program.clone(), vec![], *backend);
+                let mut blocks = 0;
+                while let Some(_x) = amp.run_program_until_output(false) {
+                    amp.run_program_until_output(false);
+                    if amp.run_program_until_output(false) == Some(2) {
+                        blocks += 1;
+                    }
+                }
+                blocks
+            })
+        });
+    }
+    group.finish();
+}
+
+// probing a 20x20 corner of the tractor beam, one fresh program per point
+fn day19(c: &mut Criterion) {
+    let program = intcode::read_data("../19Dec/program");
+    let mut group = c.benchmark_group("day19");
+    for backend in BACKENDS.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(name(*backend)), backend, |b, backend| {
+            b.iter(|| {
+                let mut affected = 0;
+                for y in 0..20 {
+                    for x in 0..20 {
+                        let mut amp = Amplifier::with_backend(program.clone(), vec![x, y], *backend);
+                        affected += amp.run_program_until_output(false).unwrap();
+                    }
+                }
+                affected
+            })
+        });
+    }
+    group.finish();
+}
+
+// booting the text adventure up to its first prompt
+fn day25(c: &mut Criterion) {
+    let program = intcode::read_data("../25Dec/program");
+    let mut group = c.benchmark_group("day25");
+    for backend in BACKENDS.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(name(*backend)), backend, |b, backend| {
+            b.iter(|| {
+                let mut amp = Amplifier::with_backend(program.clone(), vec![], *backend);
+                let mut text = String::new();
+                while !text.ends_with("Command?") {
+                    text.push(amp.run_program_until_output(false).unwrap() as u8 as char);
+                }
+                text
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, day9, day13, day19, day25);
+criterion_main!(benches);
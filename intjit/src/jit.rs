@@ -0,0 +1,507 @@
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{types, AbiParam, Block, InstBuilder, MemFlags, Value};
+use cranelift_codegen::isa::OwnedTargetIsa;
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Module};
+use intcomputer::analysis::{self, Decoded, Param};
+use std::collections::VecDeque;
+use intcomputer::intcode::{self, Event, Fault, Profile};
+use intcomputer::watch::{Access, Watchpoint};
+use std::io;
+use std::mem;
+use std::sync::{Arc, Mutex, OnceLock};
+
+// executions of a block start before it gets compiled
+const HOT: u32 = 200;
+const MAX_BLOCK_LEN: usize = 64;
+// code that got overwritten once tends to be patched again, it stays interpreted
+const MAX_INVALIDATIONS: u8 = 1;
+
+const CONTINUE: i64 = 0;
+const MODIFIED: i64 = 1;
+// the instruction at ip needs the interpreter, it faults there
+const INTERPRET: i64 = 2;
+
+// shared with the compiled code, the offsets are hardcoded in `compile`
+#[repr(C)]
+struct Context {
+    memory: *mut i64,
+    code: *const u8,
+    rb: i64,
+    ip: i64,
+    written: i64,
+}
+
+type Compiled = extern "C" fn(*mut Context) -> i64;
+
+// detecting the host is slow compared to short programs, so it is done once
+fn host_isa() -> Option<OwnedTargetIsa> {
+    static ISA: OnceLock<Option<OwnedTargetIsa>> = OnceLock::new();
+    ISA.get_or_init(|| {
+        let mut flags = settings::builder();
+        // blocks are short, compile time matters more than code quality
+        flags.set("opt_level", "none").ok()?;
+        cranelift_native::builder().ok()?
+            .finish(settings::Flags::new(flags)).ok()
+    }).clone()
+}
+
+// Compiles hot straight-line code, everything else runs on the interpreter it wraps.
+pub struct Jit {
+    amp: intcode::Amplifier,
+    profile: Profile,
+    // image cells the interpreter wrote, collected by a watchpoint
+    written: Arc<Mutex<Vec<usize>>>,
+    // created with the first compiled block, most short runs never need it
+    module: Option<JITModule>,
+    entries: Vec<Option<Compiled>>,
+    blocks: Vec<(usize, usize)>,
+    // number of compiled blocks covering each cell, checked by every write
+    code: Vec<u8>,
+    heat: Vec<u32>,
+    hot: u32,
+    invalidations: Vec<u8>,
+}
+
+impl Jit {
+    pub fn new(program: Vec<isize>, input: Vec<isize>) -> Option<Jit> {
+        if mem::size_of::<isize>() != 8 {
+            return None;
+        }
+        host_isa()?;
+
+        // only code in the loaded image gets compiled
+        let image = program.len();
+        let mut amp = intcode::Amplifier::new(program, input);
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&written);
+        amp.watch(Watchpoint::new(0..image, Access::Write).call(move |hit| {
+            log.lock().unwrap().push(hit.address);
+            false
+        }));
+        let len = amp.memory_size();
+        Some(Jit {
+            amp,
+            profile: Profile::Day9,
+            written,
+            module: None,
+            entries: vec![None; image],
+            blocks: Vec::new(),
+            code: vec![0; len],
+            heat: vec![0; image],
+            hot: HOT,
+            invalidations: vec![0; image],
+        })
+    }
+
+    pub fn set_network_mode(&mut self, new_setting: bool) {
+        self.amp.set_network_mode(new_setting);
+    }
+
+    pub fn push_input(&mut self, input: isize) {
+        self.amp.push_input(input);
+    }
+
+    pub fn push_input_vec(&mut self, input: Vec<isize>) {
+        self.amp.push_input_vec(input);
+    }
+
+    pub fn push_input_vec_deque(&mut self, input: VecDeque<isize>) {
+        self.amp.push_input_vec_deque(input);
+    }
+
+    pub fn get_program_clone(&self) -> Vec<isize> {
+        self.amp.get_program_clone()
+    }
+
+    pub fn set_hot_threshold(&mut self, executions: u32) {
+        self.hot = executions;
+    }
+
+    pub fn compiled_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        self.amp.set_profile(profile);
+    }
+
+    pub fn run_program(&mut self, debug: bool) -> Option<isize> {
+        let mut output = None;
+        while let Some(value) = self.run_program_until_output(debug) {
+            output = Some(value);
+        }
+        output
+    }
+
+    pub fn run_program_in_compatibility_mode(&mut self, noun: isize, verb: isize, debug: bool) -> isize {
+        self.amp.store(1, noun);
+        self.amp.store(2, verb);
+        self.invalidate_written();
+        let profile = self.profile;
+        self.set_profile(Profile::Day2);
+        self.run_program(debug);
+        self.set_profile(profile);
+        self.amp.load(0)
+    }
+
+    // like intcode::Amplifier::run_program_until_output, panics on faults and asks on stdin for input
+    pub fn run_program_until_output(&mut self, debug: bool) -> Option<isize> {
+        loop {
+            match self.run_until_event(debug) {
+                Ok(Event::Output(value)) => return Some(value),
+                Ok(Event::NeedInput) => self.prompt_input(),
+                Ok(Event::Watchpoint(_)) => (),
+                Ok(Event::Halted) => return None,
+                Err(fault) => panic!("{}", fault),
+            }
+        }
+    }
+
+    fn prompt_input(&mut self) {
+        let mut input_string = String::new();
+        println!("Please input a number.");
+        io::stdin()
+            .read_line(&mut input_string)
+            .expect("error reading line");
+        if let Ok(num) = input_string.trim().parse() {
+            self.push_input(num);
+        }
+    }
+
+    // same events and faults as the interpreter, compiled blocks hand faulting instructions back to it
+    pub fn run_until_event(&mut self, debug: bool) -> Result<Event, Fault> {
+        if self.amp.ip() >= self.amp.memory_size() {
+            return Ok(Event::Halted);
+        }
+        loop {
+            let ip = self.amp.ip();
+            if let Some(function) = self.entries.get(ip).copied().flatten() {
+                let mut context = Context {
+                    memory: self.amp.memory_mut().as_mut_ptr() as *mut i64,
+                    code: self.code.as_ptr(),
+                    rb: self.amp.relative_base() as i64,
+                    ip: 0,
+                    written: 0,
+                };
+                let status = function(&mut context);
+                self.amp.set_relative_base(context.rb as isize);
+                self.amp.set_ip(context.ip as usize);
+                match status {
+                    MODIFIED => {
+                        self.invalidate(context.written as usize);
+                        continue;
+                    }
+                    // entering the block again would bail out at the same instruction forever
+                    INTERPRET => (),
+                    _ => continue,
+                }
+            } else if ip < self.heat.len() && self.invalidations[ip] < MAX_INVALIDATIONS {
+                self.heat[ip] += 1;
+                if self.heat[ip] >= self.hot {
+                    match self.compile(ip) {
+                        Some((function, end)) => {
+                            self.entries[ip] = Some(function);
+                            self.blocks.push((ip, end));
+                            for cell in ip..end {
+                                self.code[cell] += 1;
+                            }
+                        }
+                        None => self.invalidations[ip] = MAX_INVALIDATIONS,
+                    }
+                    continue;
+                }
+            }
+            let event = self.amp.step(debug);
+            self.invalidate_written();
+            if let Some(event) = event? {
+                return Ok(event);
+            }
+        }
+    }
+
+    fn invalidate_written(&mut self) {
+        let written = mem::take(&mut *self.written.lock().unwrap());
+        for address in written {
+            if self.code[address] != 0 {
+                self.invalidate(address);
+            }
+        }
+    }
+
+    fn invalidate(&mut self, address: usize) {
+        let mut i = 0;
+        while i < self.blocks.len() {
+            let (start, end) = self.blocks[i];
+            if start <= address && address < end {
+                self.blocks.swap_remove(i);
+                self.entries[start] = None;
+                for cell in start..end {
+                    self.code[cell] -= 1;
+                }
+                self.heat[start] = 0;
+                self.invalidations[start] += 1;
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    // Straight-line run of instructions starting at `start` that the compiler
+    // handles. I/O, halts and anything that would fault are left to the interpreter.
+    fn trace(&self, start: usize) -> Vec<Decoded> {
+        let len = self.amp.memory_size() as isize;
+        let mut insts = Vec::new();
+        let mut address = start;
+        while insts.len() < MAX_BLOCK_LEN {
+            let inst = match analysis::decode(self.amp.memory(), address) {
+                Ok(inst) => inst,
+                Err(_) => break,
+            };
+            let layout = match inst.opcode {
+                1 | 2 | 5 | 6 | 7 | 8 | 9 if self.profile.allows(inst.opcode as usize) =>
+                    analysis::params(inst.opcode).unwrap(),
+                _ => break,
+            };
+            let compilable = layout.iter().enumerate().all(|(i, param)| {
+                let in_range = inst.params[i] >= 0 && inst.params[i] < len;
+                match inst.modes[i] {
+                    1 => *param != Param::Write,
+                    0 => in_range,
+                    _ => true,
+                }
+            });
+            if !compilable {
+                break;
+            }
+            address += inst.width();
+            let jump = inst.opcode == 5 || inst.opcode == 6;
+            insts.push(inst);
+            if jump {
+                break;
+            }
+        }
+        insts
+    }
+
+    fn compile(&mut self, start: usize) -> Option<(Compiled, usize)> {
+        let insts = self.trace(start);
+        let last = insts.last()?;
+        let end = last.address + last.width();
+        // the counters in `code` are bytes, a cell covered by 255 blocks gets no more
+        if self.code[start..end].contains(&u8::MAX) {
+            return None;
+        }
+
+        if self.module.is_none() {
+            let builder = JITBuilder::with_isa(host_isa()?, default_libcall_names());
+            self.module = Some(JITModule::new(builder));
+        }
+        let module = self.module.as_mut().unwrap();
+        let pointer = module.target_config().pointer_type();
+        let mut ctx = module.make_context();
+        ctx.func.signature.params.push(AbiParam::new(pointer));
+        ctx.func.signature.returns.push(AbiParam::new(types::I64));
+        let mut builder_context = FunctionBuilderContext::new();
+        {
+            let mut b = FunctionBuilder::new(&mut ctx.func, &mut builder_context);
+            let entry = b.create_block();
+            b.append_block_params_for_function_params(entry);
+            b.switch_to_block(entry);
+            let context = b.block_params(entry)[0];
+            let flags = MemFlags::trusted();
+            let memory = b.ins().load(pointer, flags, context, 0);
+            let code = b.ins().load(pointer, flags, context, 8);
+            let rb = Variable::from_u32(0);
+            b.declare_var(rb, types::I64);
+            let initial_rb = b.ins().load(types::I64, flags, context, 16);
+            b.def_var(rb, initial_rb);
+
+            let exit = b.create_block();
+            for _ in 0..3 {
+                b.append_block_param(exit, types::I64);
+            }
+
+            let mut emitter = Emitter { b, memory, code, rb, exit, len: self.amp.memory_size() as i64 };
+            for inst in insts.iter() {
+                emitter.instruction(inst);
+            }
+            if !(last.opcode == 5 || last.opcode == 6) {
+                emitter.leave(end as i64, CONTINUE, None);
+            }
+
+            let mut b = emitter.b;
+            b.switch_to_block(exit);
+            let params = b.block_params(exit).to_vec();
+            let rb = b.use_var(rb);
+            b.ins().store(flags, rb, context, 16);
+            b.ins().store(flags, params[0], context, 24);
+            b.ins().store(flags, params[2], context, 32);
+            b.ins().return_(&[params[1]]);
+            b.seal_all_blocks();
+            b.finalize();
+        }
+
+        let id = module.declare_anonymous_function(&ctx.func.signature).ok()?;
+        module.define_function(id, &mut ctx).ok()?;
+        module.clear_context(&mut ctx);
+        module.finalize_definitions().ok()?;
+        let function = module.get_finalized_function(id);
+        Some((unsafe { mem::transmute::<*const u8, Compiled>(function) }, end))
+    }
+}
+
+// compiled code lives as long as the module, that is until the VM goes away
+impl Drop for Jit {
+    fn drop(&mut self) {
+        if let Some(module) = self.module.take() {
+            unsafe { module.free_memory() };
+        }
+    }
+}
+
+struct Emitter<'a> {
+    b: FunctionBuilder<'a>,
+    memory: Value,
+    code: Value,
+    rb: Variable,
+    exit: Block,
+    len: i64,
+}
+
+impl<'a> Emitter<'a> {
+    fn leave(&mut self, ip: i64, status: i64, written: Option<Value>) {
+        let ip = self.b.ins().iconst(types::I64, ip);
+        let status = self.b.ins().iconst(types::I64, status);
+        let written = written.unwrap_or_else(|| self.b.ins().iconst(types::I64, 0));
+        self.b.ins().jump(self.exit, &[ip, status, written]);
+    }
+
+    // leaves the block before `inst` if `fault` is set, the interpreter then reports it
+    fn bail_if(&mut self, inst: &Decoded, fault: Value) {
+        let bail = self.b.create_block();
+        let next = self.b.create_block();
+        self.b.ins().brif(fault, bail, &[], next, &[]);
+        self.b.switch_to_block(bail);
+        self.leave(inst.address as i64, INTERPRET, None);
+        self.b.switch_to_block(next);
+    }
+
+    // memory index of a position or relative parameter, leaves the block
+    // before `inst` if a relative one overflows or is out of range
+    fn index(&mut self, inst: &Decoded, i: usize) -> Value {
+        if inst.modes[i] == 0 {
+            return self.b.ins().iconst(types::I64, inst.params[i] as i64);
+        }
+        let rb = self.b.use_var(self.rb);
+        let param = self.b.ins().iconst(types::I64, inst.params[i] as i64);
+        let (index, overflow) = self.b.ins().sadd_overflow(rb, param);
+        let outside = self.b.ins().icmp_imm(IntCC::UnsignedGreaterThanOrEqual, index, self.len);
+        let fault = self.b.ins().bor(overflow, outside);
+        self.bail_if(inst, fault);
+        index
+    }
+
+    fn cell(&mut self, index: Value) -> Value {
+        let offset = self.b.ins().ishl_imm(index, 3);
+        self.b.ins().iadd(self.memory, offset)
+    }
+
+    fn load(&mut self, inst: &Decoded, i: usize) -> Value {
+        if inst.modes[i] == 1 {
+            return self.b.ins().iconst(types::I64, inst.params[i] as i64);
+        }
+        let index = self.index(inst, i);
+        let cell = self.cell(index);
+        self.b.ins().load(types::I64, MemFlags::trusted(), cell, 0)
+    }
+
+    // writes `value` and leaves the block if the write hit compiled code
+    fn store(&mut self, inst: &Decoded, i: usize, value: Value) {
+        let index = self.index(inst, i);
+        let cell = self.cell(index);
+        self.b.ins().store(MemFlags::trusted(), value, cell, 0);
+        let flag = self.b.ins().iadd(self.code, index);
+        let flag = self.b.ins().uload8(types::I32, MemFlags::trusted(), flag, 0);
+        let modified = self.b.create_block();
+        let next = self.b.create_block();
+        self.b.ins().brif(flag, modified, &[], next, &[]);
+        self.b.switch_to_block(modified);
+        self.leave((inst.address + inst.width()) as i64, MODIFIED, Some(index));
+        self.b.switch_to_block(next);
+    }
+
+    fn instruction(&mut self, inst: &Decoded) {
+        let next = (inst.address + inst.width()) as i64;
+        match inst.opcode {
+            1 | 2 | 7 | 8 => {
+                let a = self.load(inst, 0);
+                let b = self.load(inst, 1);
+                let value = match inst.opcode {
+                    1 | 2 => {
+                        let (value, overflow) = if inst.opcode == 1 {
+                            self.b.ins().sadd_overflow(a, b)
+                        } else {
+                            self.b.ins().smul_overflow(a, b)
+                        };
+                        self.bail_if(inst, overflow);
+                        value
+                    }
+                    7 => {
+                        let c = self.b.ins().icmp(IntCC::SignedLessThan, a, b);
+                        self.b.ins().uextend(types::I64, c)
+                    }
+                    _ => {
+                        let c = self.b.ins().icmp(IntCC::Equal, a, b);
+                        self.b.ins().uextend(types::I64, c)
+                    }
+                };
+                self.store(inst, 2, value);
+            }
+            5 | 6 => {
+                let condition = self.load(inst, 0);
+                let target = self.load(inst, 1);
+                let nonzero = self.b.create_block();
+                let zero = self.b.create_block();
+                self.b.ins().brif(condition, nonzero, &[], zero, &[]);
+                let (taken, not_taken) = if inst.opcode == 5 { (nonzero, zero) } else { (zero, nonzero) };
+                self.b.switch_to_block(taken);
+                // a negative target faults in the interpreter
+                let negative = self.b.ins().icmp_imm(IntCC::SignedLessThan, target, 0);
+                self.bail_if(inst, negative);
+                let status = self.b.ins().iconst(types::I64, CONTINUE);
+                let written = self.b.ins().iconst(types::I64, 0);
+                self.b.ins().jump(self.exit, &[target, status, written]);
+                self.b.switch_to_block(not_taken);
+                self.leave(next, CONTINUE, None);
+            }
+            _ => {
+                let offset = self.load(inst, 0);
+                let rb = self.b.use_var(self.rb);
+                let (rb, overflow) = self.b.ins().sadd_overflow(rb, offset);
+                self.bail_if(inst, overflow);
+                self.b.def_var(self.rb, rb);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jit::Jit;
+
+    #[test]
+    fn saturated_code_cells_are_not_compiled() {
+        // counts [20] up to 5 and outputs it
+        let program = vec![1001, 20, 1, 20, 1007, 20, 5, 21, 1005, 21, 0, 4, 20, 99];
+        let mut jit = Jit::new(program, vec![]).unwrap();
+        jit.set_hot_threshold(1);
+        jit.code[4] = u8::MAX;
+        assert_eq!(Some(5), jit.run_program(false));
+        assert!(jit.compiled_blocks() > 0);
+        assert!(jit.blocks.iter().all(|(start, end)| !(*start..*end).contains(&4)));
+    }
+}
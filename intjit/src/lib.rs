@@ -0,0 +1,222 @@
+pub mod jit;
+
+use intcomputer::intcode;
+use std::collections::VecDeque;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Backend {
+    Interpreter,
+    Jit,
+}
+
+// Same interface as intcode::Amplifier, with the execution engine picked at construction.
+pub enum Amplifier {
+    Interpreter(Box<intcode::Amplifier>),
+    Jit(Box<jit::Jit>),
+}
+
+impl Amplifier {
+    pub fn new(program: Vec<isize>, input: Vec<isize>) -> Amplifier {
+        Amplifier::with_backend(program, input, Backend::Jit)
+    }
+
+    // falls back to the interpreter if the host is not supported by the JIT
+    pub fn with_backend(program: Vec<isize>, input: Vec<isize>, backend: Backend) -> Amplifier {
+        if backend == Backend::Jit {
+            if let Some(jit) = jit::Jit::new(program.clone(), input.clone()) {
+                return Amplifier::Jit(Box::new(jit));
+            }
+        }
+        Amplifier::Interpreter(Box::new(intcode::Amplifier::new(program, input)))
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self {
+            Amplifier::Interpreter(_) => Backend::Interpreter,
+            Amplifier::Jit(_) => Backend::Jit,
+        }
+    }
+
+    pub fn set_network_mode(&mut self, new_setting: bool) {
+        match self {
+            Amplifier::Interpreter(amp) => amp.set_network_mode(new_setting),
+            Amplifier::Jit(jit) => jit.set_network_mode(new_setting),
+        }
+    }
+
+    pub fn push_input(&mut self, input: isize) {
+        match self {
+            Amplifier::Interpreter(amp) => amp.push_input(input),
+            Amplifier::Jit(jit) => jit.push_input(input),
+        }
+    }
+
+    pub fn push_input_vec(&mut self, input: Vec<isize>) {
+        match self {
+            Amplifier::Interpreter(amp) => amp.push_input_vec(input),
+            Amplifier::Jit(jit) => jit.push_input_vec(input),
+        }
+    }
+
+    pub fn push_input_vec_deque(&mut self, input: VecDeque<isize>) {
+        match self {
+            Amplifier::Interpreter(amp) => amp.push_input_vec_deque(input),
+            Amplifier::Jit(jit) => jit.push_input_vec_deque(input),
+        }
+    }
+
+    pub fn run_program(&mut self, debug: bool) -> Option<isize> {
+        match self {
+            Amplifier::Interpreter(amp) => amp.run_program(debug),
+            Amplifier::Jit(jit) => jit.run_program(debug),
+        }
+    }
+
+    pub fn run_program_until_output(&mut self, debug: bool) -> Option<isize> {
+        match self {
+            Amplifier::Interpreter(amp) => amp.run_program_until_output(debug),
+            Amplifier::Jit(jit) => jit.run_program_until_output(debug),
+        }
+    }
+
+    pub fn run_program_in_compatibility_mode(&mut self, noun: isize, verb: isize, debug: bool) -> isize {
+        match self {
+            Amplifier::Interpreter(amp) => amp.run_program_in_compatibility_mode(noun, verb, debug),
+            Amplifier::Jit(jit) => jit.run_program_in_compatibility_mode(noun, verb, debug),
+        }
+    }
+
+    pub fn run_until_event(&mut self, debug: bool) -> Result<intcode::Event, intcode::Fault> {
+        match self {
+            Amplifier::Interpreter(amp) => amp.run_until_event(debug),
+            Amplifier::Jit(jit) => jit.run_until_event(debug),
+        }
+    }
+
+    pub fn get_program_clone(&self) -> Vec<isize> {
+        match self {
+            Amplifier::Interpreter(amp) => amp.get_program_clone(),
+            Amplifier::Jit(jit) => jit.get_program_clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Amplifier, Backend};
+    use intcomputer::intcode::{self, Fault};
+
+    fn outputs(program: &[isize], input: Vec<isize>, backend: Backend) -> Vec<isize> {
+        let mut amp = Amplifier::with_backend(program.to_vec(), input, backend);
+        let mut outputs = Vec::new();
+        while let Some(output) = amp.run_program_until_output(false) {
+            outputs.push(output);
+        }
+        outputs
+    }
+
+    #[test]
+    fn day9_matches_interpreter() {
+        let program = intcode::read_data("../intcomputer/9");
+        for input in 1..3 {
+            assert_eq!(outputs(&program, vec![input], Backend::Interpreter),
+                       outputs(&program, vec![input], Backend::Jit));
+        }
+    }
+
+    #[test]
+    fn day9_part2_gets_compiled() {
+        let program = intcode::read_data("../intcomputer/9");
+        let mut jit = crate::jit::Jit::new(program, vec![2]).unwrap();
+        assert_eq!(Some(46470), jit.run_program(false));
+        assert!(jit.compiled_blocks() > 0);
+    }
+
+    #[test]
+    fn day5_matches_interpreter() {
+        let program = intcode::read_data("../intcomputer/5");
+        for input in [1, 5].iter() {
+            assert_eq!(outputs(&program, vec![*input], Backend::Interpreter),
+                       outputs(&program, vec![*input], Backend::Jit));
+        }
+    }
+
+    #[test]
+    fn compatibility_mode() {
+        let program = intcode::read_data("../intcomputer/2");
+        let mut amp = Amplifier::with_backend(program, vec![], Backend::Jit);
+        assert_eq!(3516593, amp.run_program_in_compatibility_mode(12, 2, false));
+    }
+
+    fn eager_outputs(program: &[isize]) -> Vec<isize> {
+        let mut jit = crate::jit::Jit::new(program.to_vec(), vec![]).unwrap();
+        jit.set_hot_threshold(2);
+        let mut outputs = Vec::new();
+        while let Some(output) = jit.run_program_until_output(false) {
+            outputs.push(output);
+        }
+        outputs
+    }
+
+    #[test]
+    fn self_modifying_loop() {
+        // 0: [100] += K; 4: K += 1 (K is the operand at 2); 8: [101] = K < 20; 12: jt [101], 0; 15: out [100]
+        let program = vec![1001, 100, 0, 100, 1001, 2, 1, 2, 1007, 2, 20, 101, 1005, 101, 0, 4, 100, 99];
+        assert_eq!(vec![190], eager_outputs(&program));
+    }
+
+    #[test]
+    fn guard_invalidates_other_blocks() {
+        // hot loop at 4 adds [200] to [100], after 20 rounds the add is rewritten into a mul
+        let mut program = vec![
+            1106, 0, 4, 99,            // 0: jump to 4
+            1, 100, 200, 100,          // 4: [100] += [200]
+            1001, 101, 1, 101,         // 8: [101] += 1
+            1008, 101, 20, 103,        // 12: [103] = [101] == 20
+            1006, 103, 23,             // 16: jf [103], 23
+            1101, 0, 2, 4,             // 19: [4] = 2, turns the add at 4 into a mul
+            1007, 101, 30, 103,        // 23: [103] = [101] < 30
+            1005, 103, 4,              // 27: jt [103], 4
+            4, 100, 99,                // 30: out [100]
+        ];
+        program.resize(201, 0);
+        program[100] = 1;
+        program[200] = 3;
+        assert_eq!(vec![(1 + 3 * 20) * 3isize.pow(10)], eager_outputs(&program));
+        assert_eq!(outputs(&program, vec![], Backend::Interpreter), eager_outputs(&program));
+    }
+
+    #[test]
+    fn relative_operand_out_of_range_faults() {
+        // the block at 0 ends with a relative write far beyond memory
+        let program = vec![109, 1_000_000, 21101, 1, 1, 0, 99];
+        let fault = Err(Fault::OutOfBounds { address: 2, index: 1_000_000 });
+        let mut jit = crate::jit::Jit::new(program.clone(), vec![]).unwrap();
+        jit.set_hot_threshold(1);
+        assert_eq!(fault, jit.run_until_event(false));
+        assert!(jit.compiled_blocks() > 0);
+        let mut amp = Amplifier::with_backend(program, vec![], Backend::Interpreter);
+        assert_eq!(fault, amp.run_until_event(false));
+    }
+
+    #[test]
+    fn faults_match_interpreter() {
+        // a compiled jump to a negative address, then an illegal opcode and an immediate write after hot loops,
+        // then compiled additions, multiplications and relative base adjustments that overflow
+        let programs = vec![
+            vec![1101, 0, 0, 20, 1001, 21, 1, 21, 1007, 21, 300, 22, 1006, 22, -1, 1105, 1, 4],
+            vec![1101, 1, 1, 20, 1001, 21, 1, 21, 1007, 21, 300, 22, 1005, 22, 4, 42],
+            vec![1001, 21, 1, 21, 1007, 21, 300, 22, 1005, 22, 0, 11101, 1, 1, 1, 99],
+            vec![1001, 20, 1 << 54, 20, 1105, 1, 0],
+            vec![1001, 21, 1, 21, 1002, 21, 1 << 53, 20, 1105, 1, 0],
+            vec![109, 1 << 54, 1105, 1, 0],
+        ];
+        for program in programs {
+            let mut interpreter = Amplifier::with_backend(program.clone(), vec![], Backend::Interpreter);
+            let mut jit = Amplifier::with_backend(program, vec![], Backend::Jit);
+            let fault = interpreter.run_until_event(false);
+            assert!(fault.is_err());
+            assert_eq!(fault, jit.run_until_event(false));
+        }
+    }
+}
@@ -0,0 +1,71 @@
+extern crate proc_macro;
+
+use intcomputer::loader;
+use proc_macro::{Span, TokenStream, TokenTree};
+use std::env;
+use std::path::Path;
+
+// include_intcode!("program") loads and checks a program at compile time and expands to a
+// `&'static [i64]` for Amplifier::from_static. The path is relative to the Cargo.toml of the
+// crate that uses the macro, text programs and binary images both work.
+#[proc_macro]
+pub fn include_intcode(input: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let (literal, span) = match tokens.as_slice() {
+        [TokenTree::Literal(literal)] => (literal.to_string(), literal.span()),
+        _ => return error(Span::call_site(), "expected a path like include_intcode!(\"program\")"),
+    };
+    let relative = match unquote(&literal) {
+        Some(relative) => relative,
+        None => return error(span, "expected a plain string literal"),
+    };
+    let dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    match expand(&Path::new(&dir).join(relative)) {
+        Ok(expansion) => expansion.parse().unwrap(),
+        Err(message) => error(span, &message),
+    }
+}
+
+fn unquote(literal: &str) -> Option<&str> {
+    let text = literal.strip_prefix('"')?.strip_suffix('"')?;
+    if text.contains('\\') || text.contains('"') {
+        return None;
+    }
+    Some(text)
+}
+
+// the include_bytes makes cargo rebuild the crate when the program changes
+fn expand(path: &Path) -> Result<String, String> {
+    let program = loader::load(path).map_err(|e| format!("{}:{}", path.display(), e))?;
+    let words: Vec<String> = program.iter().map(|word| format!("{}i64", word)).collect();
+    Ok(format!("{{ const _: &[u8] = include_bytes!({:?}); const PROGRAM: &[i64] = &[{}]; PROGRAM }}",
+               path.display().to_string(), words.join(",")))
+}
+
+fn error(span: Span, message: &str) -> TokenStream {
+    let tokens: TokenStream = format!("compile_error!({:?})", message).parse().unwrap();
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expand, unquote};
+    use std::path::Path;
+
+    #[test]
+    fn expansion() {
+        let expansion = expand(Path::new("../intcomputer/9")).unwrap();
+        assert!(expansion.contains("&[1102i64,34463338i64,34463338i64,"));
+        let error = expand(Path::new("tests/bad_token")).unwrap_err();
+        assert_eq!("tests/bad_token:2:4: invalid value \"9x\"", error);
+        assert_eq!(Some("program"), unquote("\"program\""));
+        assert_eq!(None, unquote("r\"program\""));
+        assert_eq!(None, unquote("12"));
+    }
+}
@@ -0,0 +1,12 @@
+use intcomputer::intcode::Amplifier;
+use intmacro::include_intcode;
+
+static DAY9: &[i64] = include_intcode!("../intcomputer/9");
+
+#[test]
+fn day9_from_static() {
+    let mut computer = Amplifier::from_static(DAY9, vec![1]);
+    assert_eq!(Some(3497884671), computer.run_program(false));
+    let mut computer = Amplifier::from_static(include_intcode!("../intcomputer/9"), vec![2]);
+    assert_eq!(Some(46470), computer.run_program(false));
+}
@@ -0,0 +1,231 @@
+use std::error;
+use std::fmt;
+
+// The block letters of the puzzles, 4 wide and 6 high (a few are narrower or wider)
+const SMALL: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+// and the large ones, 6 wide and 10 high
+const LARGE: [(char, &str); 15] = [
+    ('A', "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#"),
+    ('B', "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####."),
+    ('C', ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####."),
+    ('E', "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######"),
+    ('F', "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#....."),
+    ('G', ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#"),
+    ('H', "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#"),
+    ('J', "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###.."),
+    ('K', "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#"),
+    ('L', "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######"),
+    ('N', "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#"),
+    ('P', "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#....."),
+    ('R', "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#"),
+    ('X', "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#"),
+    ('Z', "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######"),
+];
+
+// A glyph that is not in the font, `column` is where it starts in the trimmed bitmap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unknown {
+    pub index: usize,
+    pub column: usize,
+    pub picture: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Empty,
+    // only 6 and 10 high letters are known
+    Height(usize),
+    // text has a `?` for every unknown glyph
+    Unknown { text: String, glyphs: Vec<Unknown> },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Empty => write!(f, "nothing to read"),
+            Error::Height(height) => write!(f, "no font is {} pixels high", height),
+            Error::Unknown { text, glyphs } => {
+                write!(f, "unknown letters in {:?}", text)?;
+                for glyph in glyphs.iter() {
+                    write!(f, "\nletter {} at column {}:\n{}", glyph.index, glyph.column, glyph.picture)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+// `#` and `█` are set, everything else is not
+pub fn parse(picture: &str) -> Vec<Vec<bool>> {
+    picture.lines().map(|line| line.chars().map(|c| c == '#' || c == '█').collect()).collect()
+}
+
+fn render(bitmap: &[Vec<bool>], columns: &[usize]) -> String {
+    let lines: Vec<String> =
+        bitmap.iter().map(|row| columns.iter().map(|x| if row[*x] { '#' } else { '.' }).collect()).collect();
+    lines.join("\n")
+}
+
+// drops blank rows at the top and bottom, rows may have different lengths
+fn trim(bitmap: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let width = bitmap.iter().map(|row| row.len()).max().unwrap_or(0);
+    let filled = |row: &&Vec<bool>| row.iter().any(|pixel| *pixel);
+    let first = bitmap.iter().position(|row| filled(&row)).unwrap_or(bitmap.len());
+    let last = bitmap.iter().rposition(|row| filled(&row)).map_or(first, |last| last + 1);
+    bitmap[first..last]
+        .iter()
+        .map(|row| {
+            let mut row = row.clone();
+            row.resize(width, false);
+            row
+        })
+        .collect()
+}
+
+// The small letters sit in cells of 5 columns, Y fills its cell without a blank column after it.
+// Bitmaps that do not fit those cells are split at blank columns, so the spacing does not matter.
+pub fn decode(bitmap: &[Vec<bool>]) -> Result<String, Error> {
+    let bitmap = trim(bitmap);
+    let font: &[(char, &str)] = match bitmap.len() {
+        0 => return Err(Error::Empty),
+        6 => &SMALL,
+        10 => &LARGE,
+        height => return Err(Error::Height(height)),
+    };
+    let font: Vec<(char, String)> = font
+        .iter()
+        .map(|(letter, picture)| {
+            let glyph = parse(picture);
+            (*letter, render(&glyph, &glyph_columns(&glyph)))
+        })
+        .collect();
+    if bitmap.len() == 6 {
+        if let Some(text) = decode_cells(&bitmap, &font, 5) {
+            return Ok(text);
+        }
+    }
+
+    let width = bitmap[0].len();
+    let blank = |x: usize| bitmap.iter().all(|row| !row[x]);
+    let mut text = String::new();
+    let mut unknown = Vec::new();
+    let mut x = 0;
+    while x < width {
+        if blank(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && !blank(x) {
+            x += 1;
+        }
+        let picture = render(&bitmap, &(start..x).collect::<Vec<_>>());
+        match font.iter().find(|(_, glyph)| *glyph == picture) {
+            Some((letter, _)) => text.push(*letter),
+            None => {
+                unknown.push(Unknown { index: text.chars().count(), column: start, picture });
+                text.push('?');
+            }
+        }
+    }
+    if unknown.is_empty() {
+        Ok(text)
+    } else {
+        Err(Error::Unknown { text, glyphs: unknown })
+    }
+}
+
+// None unless every cell that is not blank holds a known letter
+fn decode_cells(bitmap: &[Vec<bool>], font: &[(char, String)], cell: usize) -> Option<String> {
+    let width = bitmap[0].len();
+    let mut text = String::new();
+    for start in (0..width).step_by(cell) {
+        let columns: Vec<usize> =
+            (start..width.min(start + cell)).filter(|x| bitmap.iter().any(|row| row[*x])).collect();
+        if columns.is_empty() {
+            continue;
+        }
+        let picture = render(bitmap, &columns);
+        text.push(font.iter().find(|(_, glyph)| *glyph == picture)?.0);
+    }
+    Some(text)
+}
+
+// the columns of a font glyph without its blank margins
+fn glyph_columns(bitmap: &[Vec<bool>]) -> Vec<usize> {
+    (0..bitmap[0].len()).filter(|x| bitmap.iter().any(|row| row[*x])).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, parse, Error, Unknown, LARGE, SMALL};
+
+    #[test]
+    fn small() {
+        let picture = "
+ ##  ###   ##  #  #  ###   ##
+#  # #  # #  # #  #   #   #  #
+#  # ###  #    ####   #   #
+#### #  # #    #  #   #   #
+#  # #  # #  # #  #   #   #  #
+#  # ###   ##  #  #  ###   ##
+";
+        assert_eq!(Ok("ABCHIC".to_string()), decode(&parse(picture)));
+        let row = |y| SMALL.iter().map(|(_, glyph)| glyph.lines().nth(y).unwrap().to_string() + "..").collect();
+        let rows: Vec<String> = (0..6).map(row).collect();
+        let letters: String = SMALL.iter().map(|(letter, _)| *letter).collect();
+        assert_eq!(Ok(letters), decode(&parse(&rows.join("\n"))));
+    }
+
+    #[test]
+    fn y_has_no_spacer() {
+        let glyph = |letter| SMALL.iter().find(|(l, _)| *l == letter).unwrap().1;
+        let row = |y| "YHEY".chars().map(|c| format!("{:.<5}", glyph(c).lines().nth(y).unwrap())).collect();
+        let rows: Vec<String> = (0..6).map(row).collect();
+        assert_eq!(Ok("YHEY".to_string()), decode(&parse(&rows.join("\n"))));
+    }
+
+    #[test]
+    fn large() {
+        let row = |y| LARGE.iter().map(|(_, glyph)| glyph.lines().nth(y).unwrap().to_string() + "..").collect();
+        let rows: Vec<String> = (0..10).map(row).collect();
+        let letters: String = LARGE.iter().map(|(letter, _)| *letter).collect();
+        assert_eq!(Ok(letters), decode(&parse(&rows.join("\n"))));
+    }
+
+    #[test]
+    fn errors() {
+        let picture = "\n█  █ █\n█  █ █\n████ █\n█  █  \n█  █ █\n█  █ █\n\n";
+        assert_eq!(
+            Err(Error::Unknown {
+                text: "H?".to_string(),
+                glyphs: vec![Unknown { index: 1, column: 5, picture: "#\n#\n#\n.\n#\n#".to_string() }],
+            }),
+            decode(&parse(picture))
+        );
+        assert_eq!(Err(Error::Height(2)), decode(&parse("#\n#")));
+        assert_eq!(Err(Error::Empty), decode(&parse("   \n   ")));
+    }
+}